@@ -16,5 +16,8 @@ fn main() {
         println!("cargo:rustc-link-arg=-Wl,-rpath,{}", lib_path.display());
     }
 
+    // 4. Re-embed the SQL migrations whenever one is added or changed
+    println!("cargo:rerun-if-changed=migrations");
+
     tauri_build::build()
 }
@@ -0,0 +1,41 @@
+-- Vocabulary tables. IF NOT EXISTS because these were created inline before migrations existed.
+CREATE TABLE IF NOT EXISTS words (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    word TEXT NOT NULL,
+    meaning TEXT NOT NULL DEFAULT '',
+    screenshot TEXT NOT NULL DEFAULT '',
+    translated_text TEXT NOT NULL DEFAULT '',
+    source_language TEXT NOT NULL DEFAULT 'ja',
+    target_language TEXT NOT NULL DEFAULT 'en',
+    pronunciation TEXT,
+    examples TEXT NOT NULL DEFAULT '[]',
+    notes TEXT,
+    is_favorite BOOLEAN NOT NULL DEFAULT 0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX IF NOT EXISTS idx_words_word ON words (word);
+
+CREATE TABLE IF NOT EXISTS word_groups (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE,
+    description TEXT,
+    color TEXT NOT NULL DEFAULT '',
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE IF NOT EXISTS word_group_members (
+    word_id INTEGER NOT NULL REFERENCES words (id) ON DELETE CASCADE,
+    group_id INTEGER NOT NULL REFERENCES word_groups (id) ON DELETE CASCADE,
+    PRIMARY KEY (word_id, group_id)
+);
+
+CREATE TABLE IF NOT EXISTS tags (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    name TEXT NOT NULL UNIQUE
+);
+
+CREATE TABLE IF NOT EXISTS word_tags (
+    word_id INTEGER NOT NULL REFERENCES words (id) ON DELETE CASCADE,
+    tag_id INTEGER NOT NULL REFERENCES tags (id) ON DELETE CASCADE,
+    PRIMARY KEY (word_id, tag_id)
+);
@@ -0,0 +1,2 @@
+-- The placeholder todo list was replaced by the vocabulary tables.
+DROP TABLE IF EXISTS todos;
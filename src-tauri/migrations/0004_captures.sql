@@ -0,0 +1,23 @@
+-- Every OCR capture, with the screenshot saved under the app data dir.
+CREATE TABLE captures (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    image_path TEXT NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL,
+    monitor_id INTEGER NOT NULL,
+    scale_factor REAL NOT NULL DEFAULT 1.0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Recognized boxes in physical pixels of the saved image, in detection order.
+CREATE TABLE capture_boxes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    capture_id INTEGER NOT NULL REFERENCES captures (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    text TEXT NOT NULL,
+    x INTEGER NOT NULL,
+    y INTEGER NOT NULL,
+    width INTEGER NOT NULL,
+    height INTEGER NOT NULL
+);
+CREATE INDEX idx_capture_boxes_capture_id ON capture_boxes (capture_id);
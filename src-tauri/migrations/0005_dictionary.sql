@@ -0,0 +1,18 @@
+-- Offline JMdict. Entries are stored whole as JSON and found through their forms.
+CREATE TABLE dictionary_entries (
+    id INTEGER PRIMARY KEY,
+    is_common BOOLEAN NOT NULL DEFAULT 0,
+    data TEXT NOT NULL
+);
+
+-- Every kanji and reading form of an entry.
+CREATE TABLE dictionary_forms (
+    text TEXT NOT NULL,
+    entry_id INTEGER NOT NULL REFERENCES dictionary_entries (id) ON DELETE CASCADE,
+    PRIMARY KEY (text, entry_id)
+) WITHOUT ROWID;
+
+CREATE TABLE dictionary_meta (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
@@ -0,0 +1,11 @@
+-- KANJIDIC2 characters, stored whole as JSON.
+CREATE TABLE kanji (
+    literal TEXT PRIMARY KEY,
+    data TEXT NOT NULL
+);
+
+-- KanjiVG stroke paths, as a JSON array in stroke order.
+CREATE TABLE kanji_strokes (
+    literal TEXT PRIMARY KEY,
+    paths TEXT NOT NULL
+);
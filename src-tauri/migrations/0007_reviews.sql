@@ -0,0 +1,20 @@
+-- Spaced repetition schedule of every studied word. Words without a row are new.
+CREATE TABLE word_reviews (
+    word_id INTEGER PRIMARY KEY REFERENCES words (id) ON DELETE CASCADE,
+    ease REAL NOT NULL,
+    interval_days INTEGER NOT NULL,
+    repetitions INTEGER NOT NULL,
+    due_at DATETIME NOT NULL
+);
+CREATE INDEX idx_word_reviews_due_at ON word_reviews (due_at);
+
+-- Every answer given while studying, with the schedule it produced.
+CREATE TABLE review_log (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    word_id INTEGER NOT NULL REFERENCES words (id) ON DELETE CASCADE,
+    grade INTEGER NOT NULL,
+    ease REAL NOT NULL,
+    interval_days INTEGER NOT NULL,
+    reviewed_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX idx_review_log_word_id ON review_log (word_id);
@@ -0,0 +1,2 @@
+-- The OCR box a word was saved from, for its sentence and screenshot in exports.
+ALTER TABLE words ADD COLUMN source_box_id INTEGER REFERENCES capture_boxes (id) ON DELETE SET NULL;
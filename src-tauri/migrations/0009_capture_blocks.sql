@@ -0,0 +1,2 @@
+-- The block (speech bubble or paragraph) each box was read in, joined into one line in transcripts.
+ALTER TABLE capture_boxes ADD COLUMN block INTEGER;
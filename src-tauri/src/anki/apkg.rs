@@ -0,0 +1,294 @@
+//! Writer for Anki packages. An `.apkg` is a zip holding the collection as
+//! a SQLite database (`collection.anki2`, schema 11, which every Anki
+//! release still imports), the media files renamed `0`, `1`, ... and a
+//! `media` JSON map from those names back to the real file names.
+
+use super::{AnkiNote, NOTE_FIELDS};
+use crate::config::AnkiConfig;
+use crate::Result;
+use serde_json::json;
+use sha1::{Digest, Sha1};
+use sqlx::sqlite::{SqliteConnectOptions, SqliteJournalMode};
+use sqlx::{ConnectOptions, Connection};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const SCHEMA: &str = "
+    CREATE TABLE col (
+        id INTEGER PRIMARY KEY, crt INTEGER NOT NULL, mod INTEGER NOT NULL,
+        scm INTEGER NOT NULL, ver INTEGER NOT NULL, dty INTEGER NOT NULL,
+        usn INTEGER NOT NULL, ls INTEGER NOT NULL, conf TEXT NOT NULL,
+        models TEXT NOT NULL, decks TEXT NOT NULL, dconf TEXT NOT NULL, tags TEXT NOT NULL
+    );
+    CREATE TABLE notes (
+        id INTEGER PRIMARY KEY, guid TEXT NOT NULL, mid INTEGER NOT NULL,
+        mod INTEGER NOT NULL, usn INTEGER NOT NULL, tags TEXT NOT NULL,
+        flds TEXT NOT NULL, sfld INTEGER NOT NULL, csum INTEGER NOT NULL,
+        flags INTEGER NOT NULL, data TEXT NOT NULL
+    );
+    CREATE TABLE cards (
+        id INTEGER PRIMARY KEY, nid INTEGER NOT NULL, did INTEGER NOT NULL,
+        ord INTEGER NOT NULL, mod INTEGER NOT NULL, usn INTEGER NOT NULL,
+        type INTEGER NOT NULL, queue INTEGER NOT NULL, due INTEGER NOT NULL,
+        ivl INTEGER NOT NULL, factor INTEGER NOT NULL, reps INTEGER NOT NULL,
+        lapses INTEGER NOT NULL, left INTEGER NOT NULL, odue INTEGER NOT NULL,
+        odid INTEGER NOT NULL, flags INTEGER NOT NULL, data TEXT NOT NULL
+    );
+    CREATE TABLE revlog (
+        id INTEGER PRIMARY KEY, cid INTEGER NOT NULL, usn INTEGER NOT NULL,
+        ease INTEGER NOT NULL, ivl INTEGER NOT NULL, lastIvl INTEGER NOT NULL,
+        factor INTEGER NOT NULL, time INTEGER NOT NULL, type INTEGER NOT NULL
+    );
+    CREATE TABLE graves (usn INTEGER NOT NULL, oid INTEGER NOT NULL, type INTEGER NOT NULL);
+    CREATE INDEX ix_notes_usn ON notes (usn);
+    CREATE INDEX ix_cards_usn ON cards (usn);
+    CREATE INDEX ix_revlog_usn ON revlog (usn);
+    CREATE INDEX ix_cards_nid ON cards (nid);
+    CREATE INDEX ix_cards_sched ON cards (did, queue, due);
+    CREATE INDEX ix_revlog_cid ON revlog (cid);
+    CREATE INDEX ix_notes_csum ON notes (csum);";
+
+const DEFAULT_DECK_ID: i64 = 1;
+
+/// The first 8 hex digits of the SHA-1 of `text`.
+fn sha1_prefix(text: &str) -> u32 {
+    let digest = Sha1::digest(text.as_bytes());
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]])
+}
+
+/// Ids derived from the names, so importing a second export updates the
+/// same note type and deck instead of creating copies.
+fn stable_id(name: &str) -> i64 {
+    (1 << 30) + i64::from(sha1_prefix(name) % (1 << 30))
+}
+
+/// Anki's duplicate check on the sort field.
+fn field_checksum(text: &str) -> i64 {
+    i64::from(sha1_prefix(text))
+}
+
+/// Notes keep the same guid across exports so re-importing updates them.
+fn note_guid(note: &AnkiNote) -> String {
+    format!("langcapture-word-{}", note.word_id)
+}
+
+fn collection_json(config: &AnkiConfig, now: i64) -> (String, String, String, String) {
+    let deck_id = stable_id(&config.deck_name);
+    let model_id = stable_id(&config.note_type);
+
+    let conf = json!({
+        "activeDecks": [DEFAULT_DECK_ID],
+        "curDeck": DEFAULT_DECK_ID,
+        "newSpread": 0,
+        "collapseTime": 1200,
+        "timeLim": 0,
+        "estTimes": true,
+        "dueCounts": true,
+        "curModel": model_id.to_string(),
+        "nextPos": 1,
+        "sortType": "noteFld",
+        "sortBackwards": false,
+        "addToCur": true,
+    });
+
+    let fields = NOTE_FIELDS
+        .iter()
+        .enumerate()
+        .map(|(ord, name)| {
+            json!({
+                "name": name, "ord": ord, "sticky": false, "rtl": false,
+                "font": "Arial", "size": 20, "media": [],
+            })
+        })
+        .collect::<Vec<_>>();
+    let models = json!({
+        model_id.to_string(): {
+            "id": model_id,
+            "name": config.note_type,
+            "type": 0,
+            "mod": now,
+            "usn": -1,
+            "sortf": 0,
+            "did": deck_id,
+            "tmpls": [{
+                "name": "Recognition",
+                "ord": 0,
+                "qfmt": config.front_template,
+                "afmt": config.back_template,
+                "bqfmt": "",
+                "bafmt": "",
+                "did": null,
+            }],
+            "flds": fields,
+            "css": config.css,
+            "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage[utf8]{inputenc}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+            "latexPost": "\\end{document}",
+            // The card is generated as long as the Word field is filled
+            "req": [[0, "any", [0]]],
+            "tags": [],
+            "vers": [],
+        }
+    });
+
+    let deck = |id: i64, name: &str| {
+        json!({
+            "id": id, "name": name, "desc": "", "mod": now, "usn": -1,
+            "collapsed": false, "browserCollapsed": false, "dyn": 0, "conf": 1,
+            "extendNew": 0, "extendRev": 0,
+            "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0],
+        })
+    };
+    let decks = json!({
+        DEFAULT_DECK_ID.to_string(): deck(DEFAULT_DECK_ID, "Default"),
+        deck_id.to_string(): deck(deck_id, &config.deck_name),
+    });
+
+    let deck_conf = json!({
+        "1": {
+            "id": 1, "name": "Default", "mod": 0, "usn": 0, "maxTaken": 60,
+            "autoplay": true, "timer": 0, "replayq": true, "dyn": false,
+            "new": {
+                "perDay": 20, "delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500,
+                "separate": true, "order": 1, "bury": true,
+            },
+            "rev": {
+                "perDay": 100, "ease4": 1.3, "fuzz": 0.05, "minSpace": 1, "ivlFct": 1,
+                "maxIvl": 36500, "bury": true,
+            },
+            "lapse": {
+                "delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0,
+            },
+        }
+    });
+
+    (
+        conf.to_string(),
+        models.to_string(),
+        decks.to_string(),
+        deck_conf.to_string(),
+    )
+}
+
+/// Creates `collection.anki2` at `db_path` with one new card per note.
+async fn write_collection(db_path: &Path, config: &AnkiConfig, notes: &[AnkiNote]) -> Result<()> {
+    let now_ms = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64);
+    let now = now_ms / 1000;
+    let deck_id = stable_id(&config.deck_name);
+    let model_id = stable_id(&config.note_type);
+
+    // Anki reads the file on its own, so nothing may be left in a WAL
+    let mut conn = SqliteConnectOptions::new()
+        .filename(db_path)
+        .create_if_missing(true)
+        .journal_mode(SqliteJournalMode::Delete)
+        .connect()
+        .await?;
+    let mut tx = conn.begin().await?;
+
+    sqlx::query(SCHEMA).execute(&mut *tx).await?;
+
+    let (conf, models, decks, deck_conf) = collection_json(config, now);
+    sqlx::query(
+        "INSERT INTO col (id, crt, mod, scm, ver, dty, usn, ls, conf, models, decks, dconf, tags)
+         VALUES (1, $1, $2, $2, 11, 0, 0, 0, $3, $4, $5, $6, '{}')",
+    )
+    .bind(now)
+    .bind(now_ms)
+    .bind(conf)
+    .bind(models)
+    .bind(decks)
+    .bind(deck_conf)
+    .execute(&mut *tx)
+    .await?;
+
+    for (index, note) in notes.iter().enumerate() {
+        // Note and card ids only need to be unique within their table
+        let id = now_ms + index as i64;
+        sqlx::query(
+            "INSERT INTO notes (id, guid, mid, mod, usn, tags, flds, sfld, csum, flags, data)
+             VALUES ($1, $2, $3, $4, -1, $5, $6, $7, $8, 0, '')",
+        )
+        .bind(id)
+        .bind(note_guid(note))
+        .bind(model_id)
+        .bind(now)
+        .bind(format!(" {} ", note.anki_tags().join(" ")))
+        .bind(note.fields().join("\x1f"))
+        .bind(&note.word)
+        .bind(field_checksum(&note.word))
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO cards (id, nid, did, ord, mod, usn, type, queue, due, ivl, factor,
+                reps, lapses, left, odue, odid, flags, data)
+             VALUES ($1, $1, $2, 0, $3, -1, 0, 0, $4, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+        )
+        .bind(id)
+        .bind(deck_id)
+        .bind(now)
+        .bind(index as i64 + 1)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    conn.close().await?;
+    Ok(())
+}
+
+fn write_zip(path: &Path, collection: &Path, notes: &[AnkiNote]) -> Result<()> {
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut zip = ZipWriter::new(File::create(path)?);
+
+    zip.start_file("collection.anki2", options)?;
+    zip.write_all(&fs::read(collection)?)?;
+
+    let mut media_names = BTreeMap::new();
+    for media in notes.iter().filter_map(|note| note.screenshot.as_ref()) {
+        let name = media_names.len().to_string();
+        zip.start_file(name.as_str(), options)?;
+        zip.write_all(&media.data)?;
+        media_names.insert(name, media.file_name.clone());
+    }
+    zip.start_file("media", options)?;
+    zip.write_all(serde_json::to_string(&media_names)?.as_bytes())?;
+
+    zip.finish()?;
+    Ok(())
+}
+
+/// Writes `notes` as an Anki package at `path`.
+pub async fn write_package(path: &Path, config: &AnkiConfig, notes: Vec<AnkiNote>) -> Result<()> {
+    let collection = temp_collection_path();
+    let result = build_package(path, &collection, config, notes).await;
+    // The collection is only needed until it is in the zip
+    let _ = fs::remove_file(&collection);
+    result
+}
+
+async fn build_package(
+    path: &Path,
+    collection: &Path,
+    config: &AnkiConfig,
+    notes: Vec<AnkiNote>,
+) -> Result<()> {
+    write_collection(collection, config, &notes).await?;
+    let (path, collection) = (path.to_owned(), collection.to_owned());
+    tauri::async_runtime::spawn_blocking(move || write_zip(&path, &collection, &notes)).await??;
+    Ok(())
+}
+
+fn temp_collection_path() -> PathBuf {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos());
+    std::env::temp_dir().join(format!("langcapture-export-{}.anki2", nanos))
+}
@@ -0,0 +1,316 @@
+//! Client for the AnkiConnect add-on (API version 6). Decks and the note
+//! type are created when missing, and notes Anki reports as duplicates are
+//! skipped rather than failing the whole batch.
+
+use super::{AnkiNote, NOTE_FIELDS};
+use crate::config::AnkiConfig;
+use crate::models::AnkiSendResult;
+use crate::{Error, Result};
+use base64::{engine::general_purpose, Engine as _};
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+const API_VERSION: u32 = 6;
+
+/// Every AnkiConnect reply, `error` is set when the action failed.
+#[derive(Deserialize)]
+struct Response<T> {
+    result: Option<T>,
+    error: Option<String>,
+}
+
+pub struct AnkiConnect {
+    client: reqwest::Client,
+    url: String,
+}
+
+impl AnkiConnect {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            url: url.into(),
+        }
+    }
+
+    async fn invoke<T: DeserializeOwned>(&self, action: &str, params: Value) -> Result<T> {
+        let response = self
+            .client
+            .post(&self.url)
+            .json(&json!({ "action": action, "version": API_VERSION, "params": params }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json::<Response<T>>()
+            .await?;
+
+        if let Some(error) = response.error {
+            return Err(Error::AnkiConnect(format!("{}: {}", action, error)));
+        }
+        response
+            .result
+            .ok_or_else(|| Error::AnkiConnect(format!("{}: no result", action)))
+    }
+
+    async fn ensure_deck(&self, deck_name: &str) -> Result<()> {
+        let decks: Vec<String> = self.invoke("deckNames", json!({})).await?;
+        if !decks.iter().any(|deck| deck == deck_name) {
+            self.invoke::<Value>("createDeck", json!({ "deck": deck_name }))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn ensure_note_type(&self, config: &AnkiConfig) -> Result<()> {
+        let models: Vec<String> = self.invoke("modelNames", json!({})).await?;
+        if !models.iter().any(|model| model == &config.note_type) {
+            self.invoke::<Value>(
+                "createModel",
+                json!({
+                    "modelName": config.note_type,
+                    "inOrderFields": NOTE_FIELDS,
+                    "css": config.css,
+                    "isCloze": false,
+                    "cardTemplates": [{
+                        "Name": "Recognition",
+                        "Front": config.front_template,
+                        "Back": config.back_template,
+                    }],
+                }),
+            )
+            .await?;
+        }
+        Ok(())
+    }
+
+    /// Adds the notes that are not in the deck yet.
+    pub async fn send_notes(
+        &self,
+        config: &AnkiConfig,
+        notes: &[AnkiNote],
+    ) -> Result<AnkiSendResult> {
+        self.ensure_deck(&config.deck_name).await?;
+        self.ensure_note_type(config).await?;
+
+        let payloads = notes
+            .iter()
+            .map(|note| note_json(config, note))
+            .collect::<Vec<_>>();
+        let can_add: Vec<bool> = self
+            .invoke("canAddNotes", json!({ "notes": payloads }))
+            .await?;
+
+        let mut new_notes = Vec::new();
+        for ((note, payload), can_add) in notes.iter().zip(payloads).zip(&can_add) {
+            if !can_add {
+                continue;
+            }
+            if let Some(media) = &note.screenshot {
+                self.invoke::<Value>(
+                    "storeMediaFile",
+                    json!({
+                        "filename": media.file_name,
+                        "data": general_purpose::STANDARD.encode(&media.data),
+                    }),
+                )
+                .await?;
+            }
+            new_notes.push(payload);
+        }
+
+        let ids: Vec<Option<i64>> = if new_notes.is_empty() {
+            Vec::new()
+        } else {
+            self.invoke("addNotes", json!({ "notes": new_notes }))
+                .await?
+        };
+
+        let added = ids.iter().filter(|id| id.is_some()).count();
+        Ok(AnkiSendResult {
+            added,
+            duplicates: notes.len() - new_notes.len(),
+            failed: ids.len() - added,
+        })
+    }
+}
+
+fn note_json(config: &AnkiConfig, note: &AnkiNote) -> Value {
+    let fields = NOTE_FIELDS
+        .iter()
+        .zip(note.fields())
+        .map(|(name, value)| (name.to_string(), Value::String(value)))
+        .collect::<Map<_, _>>();
+    json!({
+        "deckName": config.deck_name,
+        "modelName": config.note_type,
+        "fields": fields,
+        "tags": note.anki_tags(),
+        "options": { "allowDuplicate": false, "duplicateScope": "deck" },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::anki::Media;
+    use std::sync::{Arc, Mutex};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+
+    type Requests = Arc<Mutex<Vec<(String, Value)>>>;
+
+    /// Serves AnkiConnect on a free local port, answering every action with
+    /// `reply`, and records the actions it was sent.
+    async fn fake_anki(reply: fn(&str, &Value) -> Value) -> (AnkiConnect, Requests) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Requests::default();
+
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((mut stream, _)) = listener.accept().await {
+                let body = read_request(&mut stream).await;
+                let action = body["action"].as_str().unwrap().to_string();
+                let response = json!({ "result": reply(&action, &body["params"]), "error": null });
+                recorded
+                    .lock()
+                    .unwrap()
+                    .push((action, body["params"].clone()));
+
+                let response = response.to_string();
+                let head = format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                    response.len()
+                );
+                stream.write_all(head.as_bytes()).await.unwrap();
+                stream.write_all(response.as_bytes()).await.unwrap();
+            }
+        });
+        (AnkiConnect::new(url), requests)
+    }
+
+    /// The JSON body of one HTTP request.
+    async fn read_request(stream: &mut TcpStream) -> Value {
+        let mut data = Vec::new();
+        let mut chunk = [0u8; 4096];
+        let header_end = loop {
+            let read = stream.read(&mut chunk).await.unwrap();
+            assert!(read > 0, "connection closed before the request ended");
+            data.extend_from_slice(&chunk[..read]);
+            if let Some(end) = data.windows(4).position(|window| window == b"\r\n\r\n") {
+                break end + 4;
+            }
+        };
+        let head = String::from_utf8_lossy(&data[..header_end]).to_ascii_lowercase();
+        let length = head
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .map(|value| value.trim().parse::<usize>().unwrap())
+            .unwrap_or(0);
+        while data.len() < header_end + length {
+            let read = stream.read(&mut chunk).await.unwrap();
+            data.extend_from_slice(&chunk[..read]);
+        }
+        serde_json::from_slice(&data[header_end..header_end + length]).unwrap()
+    }
+
+    fn note(word_id: i64, word: &str, screenshot: bool) -> AnkiNote {
+        AnkiNote {
+            word_id,
+            word: word.to_string(),
+            reading: String::new(),
+            meaning: String::new(),
+            sentence: String::new(),
+            tags: Vec::new(),
+            screenshot: screenshot.then(|| Media {
+                file_name: format!("langcapture_{}.png", word_id),
+                data: vec![1, 2, 3],
+            }),
+        }
+    }
+
+    fn actions(requests: &Requests) -> Vec<String> {
+        let requests = requests.lock().unwrap();
+        requests.iter().map(|(action, _)| action.clone()).collect()
+    }
+
+    fn params(requests: &Requests, action: &str) -> Value {
+        let requests = requests.lock().unwrap();
+        let (_, params) = requests.iter().find(|(name, _)| name == action).unwrap();
+        params.clone()
+    }
+
+    #[tokio::test]
+    async fn creates_the_deck_and_note_type_and_skips_duplicates() {
+        let (anki, requests) = fake_anki(|action, params| match action {
+            "deckNames" => json!(["Default"]),
+            "modelNames" => json!(["Basic"]),
+            "createDeck" => json!(1),
+            "createModel" => json!({ "id": 2 }),
+            "canAddNotes" => json!([true, false, true]),
+            "storeMediaFile" => params["filename"].clone(),
+            "addNotes" => json!([10, 11]),
+            _ => panic!("unexpected action {}", action),
+        })
+        .await;
+        let config = AnkiConfig::default();
+        let notes = [
+            note(1, "食べる", true),
+            note(2, "飲む", true),
+            note(3, "見る", false),
+        ];
+
+        let result = anki.send_notes(&config, &notes).await.unwrap();
+
+        assert_eq!((result.added, result.duplicates, result.failed), (2, 1, 0));
+        assert_eq!(
+            actions(&requests),
+            [
+                "deckNames",
+                "createDeck",
+                "modelNames",
+                "createModel",
+                "canAddNotes",
+                "storeMediaFile",
+                "addNotes"
+            ]
+        );
+        assert_eq!(params(&requests, "createDeck")["deck"], config.deck_name);
+        let model = params(&requests, "createModel");
+        assert_eq!(model["modelName"], config.note_type);
+        assert_eq!(model["inOrderFields"], json!(NOTE_FIELDS));
+        // Only the screenshot of the note that is added is stored
+        assert_eq!(
+            params(&requests, "storeMediaFile")["filename"],
+            "langcapture_1.png"
+        );
+        let added = params(&requests, "addNotes")["notes"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|note| note["fields"]["Word"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(added, ["食べる", "見る"]);
+    }
+
+    #[tokio::test]
+    async fn sends_nothing_when_every_note_is_a_duplicate() {
+        let (anki, requests) = fake_anki(|action, _| match action {
+            "deckNames" => json!(["Default", "LangCapture"]),
+            "modelNames" => json!(["Basic", "LangCapture Vocabulary"]),
+            "canAddNotes" => json!([false, false]),
+            _ => panic!("unexpected action {}", action),
+        })
+        .await;
+        let config = AnkiConfig::default();
+        let notes = [note(1, "食べる", true), note(2, "飲む", false)];
+
+        let result = anki.send_notes(&config, &notes).await.unwrap();
+
+        assert_eq!((result.added, result.duplicates, result.failed), (0, 2, 0));
+        assert_eq!(
+            actions(&requests),
+            ["deckNames", "modelNames", "canAddNotes"]
+        );
+    }
+}
@@ -0,0 +1,217 @@
+//! Export of saved words to Anki. Every word becomes one note of a
+//! configurable note type with the fields of `NOTE_FIELDS`.
+
+pub mod apkg;
+pub mod connect;
+
+use crate::db::words::fetch_words;
+use crate::models::{AnkiExport, AnkiSendResult, Word, WordFilter};
+use crate::state::AppState;
+use crate::Result;
+use base64::{engine::general_purpose, Engine as _};
+use image::{DynamicImage, ImageFormat};
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+pub const NOTE_FIELDS: [&str; 5] = ["Word", "Reading", "Meaning", "Sentence", "Screenshot"];
+
+/// Tag added to every exported note, on top of the word's own tags.
+const EXPORT_TAG: &str = "langcapture";
+
+/// Context kept around the OCR box in the screenshot, in pixels.
+const CROP_MARGIN: i64 = 24;
+
+/// A file referenced from a note field.
+#[derive(Debug, Clone)]
+pub struct Media {
+    pub file_name: String,
+    pub data: Vec<u8>,
+}
+
+/// A saved word with everything its note needs, as plain text.
+#[derive(Debug, Clone)]
+pub struct AnkiNote {
+    pub word_id: i64,
+    pub word: String,
+    pub reading: String,
+    pub meaning: String,
+    /// Text of the OCR box the word was saved from.
+    pub sentence: String,
+    pub tags: Vec<String>,
+    /// PNG crop of the capture around the word's OCR box.
+    pub screenshot: Option<Media>,
+}
+
+impl AnkiNote {
+    /// Field values in `NOTE_FIELDS` order, as the HTML Anki stores.
+    pub fn fields(&self) -> [String; 5] {
+        [
+            escape_html(&self.word),
+            escape_html(&self.reading),
+            escape_html(&self.meaning),
+            escape_html(&self.sentence),
+            self.screenshot
+                .as_ref()
+                .map(|media| format!("<img src=\"{}\">", escape_html(&media.file_name)))
+                .unwrap_or_default(),
+        ]
+    }
+
+    /// Tags as Anki expects them, without spaces.
+    pub fn anki_tags(&self) -> Vec<String> {
+        std::iter::once(EXPORT_TAG.to_string())
+            .chain(self.tags.iter().map(|tag| tag.trim().replace(' ', "_")))
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\n', "<br>")
+}
+
+/// `text`, image path and physical rectangle of a word's source box.
+type SourceBox = (String, String, i64, i64, i64, i64);
+
+/// Notes for the given words, or for every saved word.
+pub async fn load_notes(pool: &Pool<Sqlite>, word_ids: Option<&[i64]>) -> Result<Vec<AnkiNote>> {
+    let words = fetch_words(pool, &WordFilter::default())
+        .await?
+        .into_iter()
+        .filter(|word| word_ids.is_none_or(|ids| ids.contains(&word.id)));
+
+    let mut sources = Vec::new();
+    for word in words {
+        let source_box = match word.source_box_id {
+            Some(box_id) => {
+                sqlx::query_as::<_, SourceBox>(
+                    "SELECT b.text, c.image_path, b.x, b.y, b.width, b.height
+                     FROM capture_boxes b JOIN captures c ON c.id = b.capture_id
+                     WHERE b.id = $1",
+                )
+                .bind(box_id)
+                .fetch_optional(pool)
+                .await?
+            }
+            None => None,
+        };
+        sources.push((word, source_box));
+    }
+
+    // Decoding the captures and encoding the crops is CPU bound
+    tauri::async_runtime::spawn_blocking(move || build_notes(sources)).await?
+}
+
+fn build_notes(sources: Vec<(Word, Option<SourceBox>)>) -> Result<Vec<AnkiNote>> {
+    // Several words are often saved from the same capture
+    let mut captures: HashMap<String, Option<DynamicImage>> = HashMap::new();
+
+    let mut notes = Vec::with_capacity(sources.len());
+    for (word, source_box) in sources {
+        let (sentence, screenshot) = match source_box {
+            Some((text, image_path, x, y, width, height)) => {
+                let capture = captures.entry(image_path).or_insert_with_key(|path| {
+                    image::open(path)
+                        .inspect_err(|err| println!("Failed to open capture {}: {}", path, err))
+                        .ok()
+                });
+                let screenshot = match capture {
+                    Some(capture) => Some(crop_png(capture, x, y, width, height)?),
+                    None => None,
+                };
+                (text, screenshot)
+            }
+            // Words added by hand only have the screenshot they were given
+            None => (
+                word.examples.first().cloned().unwrap_or_default(),
+                decode_data_url(&word.screenshot),
+            ),
+        };
+
+        notes.push(AnkiNote {
+            word_id: word.id,
+            screenshot: screenshot.map(|data| Media {
+                file_name: format!("langcapture_{}.png", word.id),
+                data,
+            }),
+            word: word.word,
+            reading: word.pronunciation.unwrap_or_default(),
+            meaning: word.meaning,
+            sentence,
+            tags: word.tags.0,
+        });
+    }
+    Ok(notes)
+}
+
+/// The box with some context around it, as PNG.
+fn crop_png(image: &DynamicImage, x: i64, y: i64, width: i64, height: i64) -> Result<Vec<u8>> {
+    let left = (x - CROP_MARGIN).max(0);
+    let top = (y - CROP_MARGIN).max(0);
+    let right = (x + width + CROP_MARGIN).min(image.width() as i64);
+    let bottom = (y + height + CROP_MARGIN).min(image.height() as i64);
+
+    let crop = image.crop_imm(
+        left as u32,
+        top as u32,
+        (right - left).max(1) as u32,
+        (bottom - top).max(1) as u32,
+    );
+    let mut bytes = Vec::new();
+    crop.write_to(&mut Cursor::new(&mut bytes), ImageFormat::Png)?;
+    Ok(bytes)
+}
+
+/// PNG bytes of a `data:image/png;base64,` screenshot.
+fn decode_data_url(screenshot: &str) -> Option<Vec<u8>> {
+    let data = screenshot.strip_prefix("data:image/png;base64,")?;
+    general_purpose::STANDARD.decode(data).ok()
+}
+
+/// Writes the given words, or every saved word, to an `.apkg` at `path`,
+/// by default a dated file in the downloads folder.
+#[tauri::command]
+pub async fn export_anki(
+    path: Option<String>,
+    word_ids: Option<Vec<i64>>,
+    app: AppHandle,
+    state: State<'_, AppState>,
+) -> Result<AnkiExport> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => app.path().download_dir()?.join(format!(
+            "langcapture_{}.apkg",
+            chrono::Local::now().format("%Y%m%d_%H%M%S")
+        )),
+    };
+
+    let anki = state.config.lock().unwrap().anki.clone();
+    let notes = load_notes(&state.db, word_ids.as_deref()).await?;
+    let note_count = notes.len();
+    apkg::write_package(&path, &anki, notes).await?;
+
+    Ok(AnkiExport {
+        path: path.to_string_lossy().into_owned(),
+        note_count,
+    })
+}
+
+/// Adds the given words, or every saved word, to Anki through AnkiConnect.
+#[tauri::command]
+pub async fn send_to_anki(
+    word_ids: Option<Vec<i64>>,
+    state: State<'_, AppState>,
+) -> Result<AnkiSendResult> {
+    let anki = state.config.lock().unwrap().anki.clone();
+    let notes = load_notes(&state.db, word_ids.as_deref()).await?;
+    connect::AnkiConnect::new(&anki.connect_url)
+        .send_notes(&anki, &notes)
+        .await
+}
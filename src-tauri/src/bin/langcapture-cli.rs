@@ -0,0 +1,233 @@
+//! Batch OCR of saved images, printing every detected box as a JSON line.
+//!
+//! Usage: langcapture-cli [--resources <dir>] [--reading-order <order>]
+//!                        [--preprocessing <strategy>] <file | directory | ->...
+
+use image::DynamicImage;
+use langcapture::{
+    models::{OcrBox, Preprocessing, ReadingOrder},
+    ocr::{init_ort_from_dir, layout, OcrEngine, OcrModelPaths},
+};
+use serde::Serialize;
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+const IMAGE_EXTENSIONS: [&str; 5] = ["png", "jpg", "jpeg", "bmp", "webp"];
+
+#[derive(Serialize)]
+struct OcrLine<'a> {
+    source: &'a str,
+    /// Index of the line's block within the image.
+    block: usize,
+    #[serde(flatten)]
+    ocr_box: &'a OcrBox,
+}
+
+enum Input {
+    File(PathBuf),
+    Stdin,
+}
+
+impl Input {
+    fn name(&self) -> String {
+        match self {
+            Input::File(path) => path.display().to_string(),
+            Input::Stdin => "-".to_string(),
+        }
+    }
+
+    fn load(&self) -> langcapture::Result<DynamicImage> {
+        match self {
+            Input::File(path) => Ok(image::open(path)?),
+            Input::Stdin => {
+                let mut bytes = Vec::new();
+                io::stdin().read_to_end(&mut bytes)?;
+                Ok(image::load_from_memory(&bytes)?)
+            }
+        }
+    }
+}
+
+fn usage() -> &'static str {
+    concat!(
+        "Usage: langcapture-cli [options] <file | directory | ->...\n",
+        "\n",
+        "Runs PP-OCRv5 detection and Manga-OCR recognition and prints one JSON object per box,\n",
+        "in reading order.\n",
+        "\n",
+        "Options:\n",
+        "  --resources <dir>           Folder containing the app's `resources` folder (defaults to\n",
+        "                              the executable's directory, or $LANGCAPTURE_RESOURCES when set)\n",
+        "  --reading-order <order>     auto (default), vertical-rl or horizontal-tb\n",
+        "  --preprocessing <strategy>  auto (default), none, otsu or sauvola",
+    )
+}
+
+/// Same lookup Tauri uses for `resource_dir()` on desktop: the folder of the running executable.
+fn default_resource_dir() -> io::Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("LANGCAPTURE_RESOURCES") {
+        return Ok(PathBuf::from(dir));
+    }
+    let exe = std::env::current_exe()?;
+    Ok(exe.parent().map(Path::to_path_buf).unwrap_or_default())
+}
+
+fn is_image(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn collect_inputs(args: &[String]) -> io::Result<Vec<Input>> {
+    let mut inputs = Vec::new();
+    for arg in args {
+        if arg == "-" {
+            inputs.push(Input::Stdin);
+            continue;
+        }
+
+        let path = PathBuf::from(arg);
+        if path.is_dir() {
+            let mut files = std::fs::read_dir(&path)?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|p| p.is_file() && is_image(p))
+                .collect::<Vec<_>>();
+            files.sort();
+            inputs.extend(files.into_iter().map(Input::File));
+        } else {
+            inputs.push(Input::File(path));
+        }
+    }
+    Ok(inputs)
+}
+
+fn main() -> ExitCode {
+    let mut resource_dir = None;
+    let mut reading_order = ReadingOrder::default();
+    let mut preprocessing = Preprocessing::default();
+    let mut paths = Vec::new();
+
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => {
+                println!("{}", usage());
+                return ExitCode::SUCCESS;
+            }
+            "--resources" => match args.next() {
+                Some(dir) => resource_dir = Some(PathBuf::from(dir)),
+                None => {
+                    eprintln!("--resources requires a directory\n\n{}", usage());
+                    return ExitCode::from(2);
+                }
+            },
+            "--reading-order" => {
+                let order = args
+                    .next()
+                    .and_then(|order| serde_json::from_value(order.into()).ok());
+                match order {
+                    Some(order) => reading_order = order,
+                    None => {
+                        eprintln!("--reading-order requires a valid order\n\n{}", usage());
+                        return ExitCode::from(2);
+                    }
+                }
+            }
+            "--preprocessing" => {
+                let strategy = args
+                    .next()
+                    .and_then(|strategy| serde_json::from_value(strategy.into()).ok());
+                match strategy {
+                    Some(strategy) => preprocessing = strategy,
+                    None => {
+                        eprintln!("--preprocessing requires a valid strategy\n\n{}", usage());
+                        return ExitCode::from(2);
+                    }
+                }
+            }
+            _ => paths.push(arg),
+        }
+    }
+
+    if paths.is_empty() {
+        eprintln!("{}", usage());
+        return ExitCode::from(2);
+    }
+
+    let resource_dir = match resource_dir.map(Ok).unwrap_or_else(default_resource_dir) {
+        Ok(dir) => dir,
+        Err(err) => {
+            eprintln!("Failed to resolve the resource directory: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let inputs = match collect_inputs(&paths) {
+        Ok(inputs) => inputs,
+        Err(err) => {
+            eprintln!("Failed to read inputs: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+
+    let engine = init_ort_from_dir(&OcrModelPaths::onnx_lib_dir(&resource_dir))
+        .and_then(|_| OcrEngine::new(&OcrModelPaths::from_resource_dir(&resource_dir)));
+    let mut engine = match engine {
+        Ok(engine) => engine,
+        Err(err) => {
+            eprintln!("Failed to load OCR models: {}", err);
+            return ExitCode::FAILURE;
+        }
+    };
+    engine.set_preprocessing(preprocessing);
+
+    let mut stdout = BufWriter::new(io::stdout().lock());
+    let mut failed = false;
+
+    for input in inputs {
+        let source = input.name();
+        let boxes = input
+            .load()
+            .and_then(|image| engine.recognize_image(&image));
+
+        match boxes {
+            Ok(mut boxes) => {
+                let blocks = layout::group_blocks(&mut boxes, reading_order);
+                let lines = blocks.iter().enumerate().flat_map(|(block, text_block)| {
+                    text_block.lines.iter().map(move |&index| (block, index))
+                });
+                for (block, index) in lines {
+                    let line = OcrLine {
+                        source: &source,
+                        block,
+                        ocr_box: &boxes[index],
+                    };
+                    let written = serde_json::to_writer(&mut stdout, &line)
+                        .map_err(io::Error::from)
+                        .and_then(|_| writeln!(stdout));
+                    if let Err(err) = written {
+                        eprintln!("Failed to write output: {}", err);
+                        return ExitCode::FAILURE;
+                    }
+                }
+            }
+            Err(err) => {
+                eprintln!("{}: {}", source, err);
+                failed = true;
+            }
+        }
+    }
+
+    if let Err(err) = stdout.flush() {
+        eprintln!("Failed to write output: {}", err);
+        return ExitCode::FAILURE;
+    }
+
+    if failed {
+        ExitCode::FAILURE
+    } else {
+        ExitCode::SUCCESS
+    }
+}
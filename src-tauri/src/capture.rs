@@ -0,0 +1,340 @@
+use crate::{
+    config,
+    db::captures::save_capture,
+    models::{CaptureMode, MonitorInfo, OcrResult, ReadingOrder, Rect},
+    ocr::{layout, run_ocr},
+    state::AppState,
+    Result,
+};
+use image::{DynamicImage, RgbaImage};
+use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Emitter, Manager, State, WebviewWindow};
+use xcap::Monitor;
+
+/// A screenshot taken when the shortcut was pressed, waiting for the user
+/// to pick a region on the overlay.
+pub struct PendingCapture {
+    pub monitor_id: u32,
+    /// See `monitor_key`.
+    pub monitor_key: String,
+    pub scale_factor: f64,
+    pub image: RgbaImage,
+}
+
+pub async fn take_screenshot(app: &AppHandle, capture_mode: CaptureMode) {
+    let Some(window) = app.get_webview_window("overlay") else {
+        println!("Overlay window not found!");
+        return;
+    };
+
+    if window.is_visible().unwrap_or(false) {
+        window.hide().unwrap();
+        app.state::<AppState>().pending_capture.lock().unwrap().take();
+        app.emit("reset-ocr", ()).unwrap();
+        return;
+    }
+
+    // Capture before the overlay is shown so it never ends up in the image
+    let (capture, window_monitor) = match capture_under_cursor(app) {
+        Ok(capture) => capture,
+        Err(err) => {
+            app.emit("error", err.to_string()).unwrap();
+            return;
+        }
+    };
+    let monitor_id = capture.monitor_id;
+
+    if let Some(window_monitor) = window_monitor {
+        if let Err(err) = place_overlay(&window, &window_monitor) {
+            println!("Failed to move the overlay to the captured monitor: {}", err);
+        }
+    }
+
+    let state = app.state::<AppState>();
+    let (ocr_bounds, reading_order) = {
+        let config = state.config.lock().unwrap();
+        (config.ocr_bounds.clone(), config.reading_order)
+    };
+    let full_capture = match capture_mode {
+        CaptureMode::Full => Some(capture),
+        CaptureMode::Region => {
+            *state.pending_capture.lock().unwrap() = Some(capture);
+            None
+        }
+    };
+
+    window.show().unwrap();
+    window.set_focus().unwrap();
+    window.set_always_on_top(true).unwrap();
+
+    match full_capture {
+        Some(capture) => {
+            let region = bounds_region(&ocr_bounds, &capture);
+            let result = ocr_capture(app, capture, region, reading_order).await;
+            finish_capture(app, result).await;
+        }
+        None => {
+            app.emit("select-region", monitor_id).unwrap();
+        }
+    }
+}
+
+/// Captures the monitor under the cursor, also returning it as the Tauri
+/// monitor used to place the overlay.
+fn capture_under_cursor(app: &AppHandle) -> Result<(PendingCapture, Option<tauri::Monitor>)> {
+    let cursor = app.cursor_position()?;
+    let window_monitor = match app.monitor_from_point(cursor.x, cursor.y)? {
+        Some(window_monitor) => Some(window_monitor),
+        None => app.primary_monitor()?,
+    };
+
+    // xcap works in points on macOS and in physical pixels everywhere else
+    let point_scale = if cfg!(target_os = "macos") {
+        window_monitor.as_ref().map_or(1.0, |m| m.scale_factor())
+    } else {
+        1.0
+    };
+    let monitor = match Monitor::from_point(
+        (cursor.x / point_scale) as i32,
+        (cursor.y / point_scale) as i32,
+    ) {
+        Ok(monitor) => monitor,
+        Err(_) => primary_monitor()?,
+    };
+
+    Ok((capture_monitor(&monitor)?, window_monitor))
+}
+
+/// Names a monitor the same way across restarts, unlike its id, which is
+/// a display handle that Windows hands out anew. Used to key per-monitor settings.
+pub fn monitor_key(monitor: &Monitor) -> String {
+    format!(
+        "{} {}x{}@{},{}",
+        monitor.name(),
+        monitor.width(),
+        monitor.height(),
+        monitor.x(),
+        monitor.y()
+    )
+}
+
+/// The key of the primary monitor, if one can be found.
+pub fn primary_monitor_key() -> Option<String> {
+    primary_monitor().ok().map(|monitor| monitor_key(&monitor))
+}
+
+fn primary_monitor() -> Result<Monitor> {
+    let mut monitors = Monitor::all()?;
+    if monitors.is_empty() {
+        return Err("No monitor found".into());
+    }
+    let index = monitors.iter().position(Monitor::is_primary).unwrap_or(0);
+    Ok(monitors.swap_remove(index))
+}
+
+/// Moves the overlay so it covers exactly `monitor`.
+fn place_overlay(window: &WebviewWindow, monitor: &tauri::Monitor) -> Result<()> {
+    // A maximized window would stay on its previous monitor
+    window.unmaximize()?;
+    window.set_position(*monitor.position())?;
+    window.set_size(*monitor.size())?;
+    Ok(())
+}
+
+/// Sends the result to the overlay, then records successful captures in the history.
+async fn finish_capture(app: &AppHandle, ocr_box: Result<(DynamicImage, OcrResult)>) {
+    match ocr_box {
+        Ok((image, bbox)) => {
+            // The boxes are shown right away, saving the screenshot takes a while
+            app.emit("run-ocr", &bbox).unwrap();
+            match save_capture(app, image, &bbox).await {
+                Ok(saved) => app.emit("capture-saved", saved).unwrap(),
+                Err(err) => println!("Failed to save the capture: {}", err),
+            }
+        }
+        Err(err) => {
+            app.emit("error", err.to_string()).unwrap();
+        }
+    }
+}
+
+/// Converts a logical rectangle into a physical crop clamped to the image,
+/// returning `None` when nothing of it is left.
+fn physical_region(
+    rect: Rect,
+    scale_factor: f64,
+    image_width: u32,
+    image_height: u32,
+) -> Option<(u32, u32, u32, u32)> {
+    let rect = rect.normalized().to_physical(scale_factor);
+    let left = rect.x.floor().max(0.0);
+    let top = rect.y.floor().max(0.0);
+    let right = (rect.x + rect.width).ceil().min(image_width as f64);
+    let bottom = (rect.y + rect.height).ceil().min(image_height as f64);
+
+    if right <= left || bottom <= top {
+        return None;
+    }
+
+    Some((
+        left as u32,
+        top as u32,
+        (right - left) as u32,
+        (bottom - top) as u32,
+    ))
+}
+
+fn capture_monitor(monitor: &Monitor) -> Result<PendingCapture> {
+    Ok(PendingCapture {
+        monitor_id: monitor.id(),
+        monitor_key: monitor_key(monitor),
+        scale_factor: monitor.scale_factor() as f64,
+        image: monitor.capture_image()?,
+    })
+}
+
+fn capture_monitor_by_id(monitor_id: u32) -> Result<PendingCapture> {
+    let monitor = Monitor::all()?
+        .into_iter()
+        .find(|monitor| monitor.id() == monitor_id)
+        .ok_or_else(|| format!("Monitor {} not found", monitor_id))?;
+
+    capture_monitor(&monitor)
+}
+
+/// OCRs `rect` (in the overlay's logical pixels) of the given monitor and
+/// emits the boxes through `run-ocr`, positioned relative to the monitor.
+/// Without a `reading_order` the configured one is used.
+#[tauri::command]
+pub async fn capture_region(
+    app: AppHandle,
+    monitor_id: u32,
+    rect: Rect,
+    reading_order: Option<ReadingOrder>,
+) -> Result<()> {
+    let processing = app.state::<AppState>().is_processing.clone();
+    if processing.swap(true, Ordering::SeqCst) {
+        return Err("OCR process is already running".into());
+    }
+
+    let reading_order = reading_order
+        .unwrap_or_else(|| app.state::<AppState>().config.lock().unwrap().reading_order);
+    let result = ocr_region(&app, monitor_id, rect, reading_order).await;
+    finish_capture(&app, result).await;
+    processing.store(false, Ordering::SeqCst);
+    Ok(())
+}
+
+async fn ocr_region(
+    app: &AppHandle,
+    monitor_id: u32,
+    rect: Rect,
+    reading_order: ReadingOrder,
+) -> Result<(DynamicImage, OcrResult)> {
+    // Prefer the frame grabbed before the overlay was shown, so the selection
+    // rectangle itself never ends up in the image.
+    let pending = app
+        .state::<AppState>()
+        .pending_capture
+        .lock()
+        .unwrap()
+        .take()
+        .filter(|capture| capture.monitor_id == monitor_id);
+    let capture = match pending {
+        Some(capture) => capture,
+        None => capture_monitor_by_id(monitor_id)?,
+    };
+
+    let (x, y, width, height) = physical_region(
+        rect,
+        capture.scale_factor,
+        capture.image.width(),
+        capture.image.height(),
+    )
+    .ok_or("Selected region is empty")?;
+
+    ocr_capture(app, capture, Some((x, y, width, height)), reading_order).await
+}
+
+/// OCRs `region` (physical pixels) of the capture, or all of it, with the
+/// boxes positioned relative to the monitor and sorted in `reading_order`.
+async fn ocr_capture(
+    app: &AppHandle,
+    capture: PendingCapture,
+    region: Option<(u32, u32, u32, u32)>,
+    reading_order: ReadingOrder,
+) -> Result<(DynamicImage, OcrResult)> {
+    let full_image = DynamicImage::ImageRgba8(capture.image);
+    let (mut boxes, x, y) = match region {
+        Some((x, y, width, height)) => {
+            let cropped_image = full_image.crop_imm(x, y, width, height);
+            (run_ocr(app, &cropped_image).await?, x, y)
+        }
+        None => (run_ocr(app, &full_image).await?, 0, 0),
+    };
+    for bbox in &mut boxes {
+        bbox.translate(x, y);
+    }
+    let blocks = layout::group_blocks(&mut boxes, reading_order);
+
+    // Boxes are relative to the monitor, so the whole frame goes to the history
+    let result = OcrResult::new(capture.monitor_id, capture.scale_factor, boxes, blocks);
+    Ok((full_image, result))
+}
+
+/// The OCR bounds configured for the captured monitor as a crop of
+/// `capture`. Bounds are logical pixels relative to that monitor, so they
+/// follow its scale factor. `None` means the whole monitor.
+fn bounds_region(
+    bounds: &HashMap<String, Rect>,
+    capture: &PendingCapture,
+) -> Option<(u32, u32, u32, u32)> {
+    let bounds = *bounds.get(&capture.monitor_key)?;
+    if bounds.width == 0.0 || bounds.height == 0.0 {
+        return None;
+    }
+    let region = physical_region(
+        bounds,
+        capture.scale_factor,
+        capture.image.width(),
+        capture.image.height(),
+    );
+    if region.is_none() {
+        println!(
+            "OCR bounds are outside of monitor {}, reading all of it",
+            capture.monitor_key
+        );
+    }
+    region
+}
+
+/// Monitors that can be captured, primary first.
+#[tauri::command]
+pub fn list_monitors() -> Result<Vec<MonitorInfo>> {
+    let mut monitors = Monitor::all()?
+        .iter()
+        .map(|monitor| MonitorInfo {
+            id: monitor.id(),
+            key: monitor_key(monitor),
+            name: monitor.name().to_string(),
+            is_primary: monitor.is_primary(),
+        })
+        .collect::<Vec<_>>();
+    monitors.sort_by_key(|monitor| !monitor.is_primary);
+    Ok(monitors)
+}
+
+#[tauri::command]
+pub fn get_capture_mode(state: State<'_, AppState>) -> CaptureMode {
+    state.config.lock().unwrap().capture_mode
+}
+
+/// Shortcut for changing only the capture mode of the saved config.
+#[tauri::command]
+pub fn set_capture_mode(mode: CaptureMode, app: AppHandle) -> Result<()> {
+    let mut config = app.state::<AppState>().config.lock().unwrap().clone();
+    config.capture_mode = mode;
+    config::update(&app, config)?;
+    Ok(())
+}
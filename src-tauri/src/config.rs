@@ -0,0 +1,365 @@
+//! User settings, saved as `settings.json` in the app data dir. The file
+//! carries a version so older files can be upgraded when fields change.
+
+use crate::capture;
+use crate::models::{CaptureMode, Preprocessing, ReadingOrder, Rect};
+use crate::shortcuts;
+use crate::state::AppState;
+use crate::{Error, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Emitter, Manager, State};
+
+const CONFIG_FILE: &str = "settings.json";
+const CONFIG_VERSION: u32 = 2;
+
+/// Emitted with the new `AppConfig` whenever the settings are saved.
+pub const CONFIG_CHANGED_EVENT: &str = "config-changed";
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum DictionaryProvider {
+    #[default]
+    FreeDictionary,
+    GoogleTranslate,
+    Custom,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Theme {
+    Light,
+    Dark,
+    #[default]
+    System,
+}
+
+/// Deck and note type of exported words. The note type always has the
+/// fields of `anki::NOTE_FIELDS`, the templates and styling are up to the user.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AnkiConfig {
+    pub deck_name: String,
+    pub note_type: String,
+    pub front_template: String,
+    pub back_template: String,
+    pub css: String,
+    /// Where the AnkiConnect add-on listens.
+    pub connect_url: String,
+}
+
+impl Default for AnkiConfig {
+    fn default() -> Self {
+        Self {
+            deck_name: "LangCapture".to_string(),
+            note_type: "LangCapture Vocabulary".to_string(),
+            front_template: "<div class=\"word\">{{Word}}</div>".to_string(),
+            back_template: "{{FrontSide}}\n<hr id=\"answer\">\n\
+                <div class=\"reading\">{{Reading}}</div>\n\
+                <div>{{Meaning}}</div>\n\
+                <div class=\"sentence\">{{Sentence}}</div>\n\
+                {{Screenshot}}"
+                .to_string(),
+            css: ".card { font-family: sans-serif; font-size: 20px; text-align: center; }\n\
+                .word { font-size: 48px; }\n\
+                .reading, .sentence { color: #666; }\n\
+                img { max-width: 100%; }"
+                .to_string(),
+            connect_url: "http://127.0.0.1:8765".to_string(),
+        }
+    }
+}
+
+/// Missing fields take their default, so settings files from older
+/// versions still load.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AppConfig {
+    pub dictionary_provider: DictionaryProvider,
+    pub custom_dictionary_url: Option<String>,
+    pub source_language: String,
+    pub target_language: String,
+    /// Part of each monitor to OCR, in logical pixels, by `capture::monitor_key`.
+    /// Monitors without bounds, or with an empty rectangle, are read whole.
+    pub ocr_bounds: HashMap<String, Rect>,
+    /// Starts a capture in `capture_mode`.
+    pub capture_shortcut: String,
+    pub capture_mode: CaptureMode,
+    /// Always starts a region capture, whatever `capture_mode` is. Unused when empty.
+    pub region_capture_shortcut: Option<String>,
+    /// Used for captures that do not pick their own reading order.
+    pub reading_order: ReadingOrder,
+    pub preprocessing: Preprocessing,
+    pub auto_save: bool,
+    pub theme: Theme,
+    pub anki: AnkiConfig,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            dictionary_provider: DictionaryProvider::default(),
+            custom_dictionary_url: None,
+            source_language: "ja".to_string(),
+            target_language: "en".to_string(),
+            ocr_bounds: HashMap::new(),
+            capture_shortcut: "Ctrl+Shift+S".to_string(),
+            capture_mode: CaptureMode::default(),
+            region_capture_shortcut: None,
+            reading_order: ReadingOrder::default(),
+            preprocessing: Preprocessing::default(),
+            auto_save: false,
+            theme: Theme::default(),
+            anki: AnkiConfig::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn validate(&self) -> Result<()> {
+        for (name, code) in [
+            ("source language", &self.source_language),
+            ("target language", &self.target_language),
+        ] {
+            if !is_language_code(code) {
+                return Err(Error::Config(format!("'{}' is not a valid {}", code, name)));
+            }
+        }
+
+        if self.dictionary_provider == DictionaryProvider::Custom
+            && !is_http_url(self.custom_dictionary_url.as_deref().unwrap_or(""))
+        {
+            return Err(Error::Config(
+                "The custom dictionary needs an http(s) URL".to_string(),
+            ));
+        }
+
+        let invalid_bounds = self.ocr_bounds.values().any(|bounds| {
+            let values = [bounds.x, bounds.y, bounds.width, bounds.height];
+            values.iter().any(|value| !value.is_finite())
+                || bounds.width < 0.0
+                || bounds.height < 0.0
+        });
+        if invalid_bounds {
+            return Err(Error::Config(
+                "OCR bounds must have a non-negative width and height".to_string(),
+            ));
+        }
+
+        shortcuts::bindings(self)?;
+
+        let anki = &self.anki;
+        if anki.deck_name.trim().is_empty() || anki.note_type.trim().is_empty() {
+            return Err(Error::Config(
+                "The Anki deck and note type need a name".to_string(),
+            ));
+        }
+        if !is_http_url(&anki.connect_url) {
+            return Err(Error::Config(
+                "The AnkiConnect address needs an http(s) URL".to_string(),
+            ));
+        }
+        if anki.front_template.trim().is_empty() {
+            return Err(Error::Config("The Anki front template is empty".to_string()));
+        }
+        Ok(())
+    }
+}
+
+fn is_http_url(url: &str) -> bool {
+    let url = url.trim();
+    url.starts_with("http://") || url.starts_with("https://")
+}
+
+/// BCP 47 style codes such as `ja`, `en` or `zh-Hant`.
+fn is_language_code(code: &str) -> bool {
+    !code.is_empty()
+        && code.len() <= 12
+        && code
+            .split('-')
+            .all(|part| !part.is_empty() && part.chars().all(|c| c.is_ascii_alphanumeric()))
+}
+
+/// The settings file: the config plus the version it was written with.
+#[derive(Serialize, Deserialize)]
+struct ConfigFile {
+    #[serde(default)]
+    version: u32,
+    #[serde(flatten)]
+    config: AppConfig,
+}
+
+fn config_path(app: &AppHandle) -> Result<PathBuf> {
+    Ok(app.path().app_data_dir()?.join(CONFIG_FILE))
+}
+
+fn read_config(path: &Path) -> Result<Option<AppConfig>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let mut json: serde_json::Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+    upgrade(&mut json, capture::primary_monitor_key);
+    let file: ConfigFile = serde_json::from_value(json)?;
+    if file.version > CONFIG_VERSION {
+        println!(
+            "Settings were written by a newer version ({}), unknown fields are ignored",
+            file.version
+        );
+    }
+    file.config.validate()?;
+    Ok(Some(file.config))
+}
+
+/// Rewrites settings written by older versions into the current layout.
+/// `primary_monitor` gives the key of the primary monitor, only asked for
+/// when old settings need it.
+fn upgrade(json: &mut serde_json::Value, primary_monitor: impl FnOnce() -> Option<String>) {
+    let version = json.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+    let Some(fields) = json.as_object_mut() else {
+        return;
+    };
+
+    // Version 1 had one set of OCR bounds for every monitor, they were
+    // only ever set up on the primary one
+    if version < 2 {
+        if let Some(bounds) = fields.remove("ocrBounds") {
+            let is_set = ["width", "height"]
+                .iter()
+                .all(|key| bounds.get(key).and_then(|v| v.as_f64()).unwrap_or(0.0) > 0.0);
+            if is_set {
+                match primary_monitor() {
+                    Some(key) => {
+                        fields.insert("ocrBounds".to_string(), serde_json::json!({ key: bounds }));
+                    }
+                    None => println!("No primary monitor to keep the old OCR bounds on"),
+                }
+            }
+        }
+    }
+}
+
+/// Reads the settings file, falling back to the defaults when it is missing
+/// or invalid so a bad file never keeps the app from starting.
+pub fn load(app: &AppHandle) -> AppConfig {
+    let config = config_path(app).and_then(|path| read_config(&path));
+    match config {
+        Ok(config) => config.unwrap_or_default(),
+        Err(err) => {
+            println!("Ignoring the settings file: {}", err);
+            AppConfig::default()
+        }
+    }
+}
+
+fn save(app: &AppHandle, config: &AppConfig) -> Result<()> {
+    let path = config_path(app)?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let json = serde_json::to_string_pretty(&ConfigFile {
+        version: CONFIG_VERSION,
+        config: config.clone(),
+    })?;
+
+    // Written aside first so a crash never leaves a half written file
+    let temp_path = path.with_extension("json.tmp");
+    fs::write(&temp_path, json)?;
+    fs::rename(&temp_path, &path)?;
+    Ok(())
+}
+
+/// Validates, saves and applies `config`, then tells every window about it.
+/// Nothing is saved when the new shortcuts cannot be registered.
+pub fn update(app: &AppHandle, config: AppConfig) -> Result<AppConfig> {
+    config.validate()?;
+
+    let state = app.state::<AppState>();
+    let previous = state.config.lock().unwrap().clone();
+    // Shortcuts that failed to register before are tried again even when unchanged
+    let was_registered = state.shortcuts_registered.load(Ordering::SeqCst);
+    let rebind =
+        !was_registered || shortcuts::bindings(&previous)? != shortcuts::bindings(&config)?;
+    // Put the old shortcuts back so captures keep working
+    let restore = || {
+        if was_registered {
+            if let Err(err) = shortcuts::register(app, &previous) {
+                println!("Failed to restore the previous shortcuts: {}", err);
+            }
+        }
+    };
+
+    if rebind {
+        if let Err(err) = shortcuts::register(app, &config) {
+            restore();
+            return Err(err);
+        }
+    }
+    if let Err(err) = save(app, &config) {
+        if rebind {
+            restore();
+        }
+        return Err(err);
+    }
+
+    *state.config.lock().unwrap() = config.clone();
+    app.emit(CONFIG_CHANGED_EVENT, config.clone())?;
+    Ok(config)
+}
+
+#[tauri::command]
+pub fn get_config(state: State<'_, AppState>) -> AppConfig {
+    state.config.lock().unwrap().clone()
+}
+
+#[tauri::command]
+pub fn set_config(config: AppConfig, app: AppHandle) -> Result<AppConfig> {
+    update(&app, config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn upgraded(mut json: serde_json::Value) -> AppConfig {
+        upgrade(&mut json, || Some("Display 1920x1080@0,0".to_string()));
+        serde_json::from_value::<ConfigFile>(json).unwrap().config
+    }
+
+    #[test]
+    fn moves_version_1_bounds_to_the_primary_monitor() {
+        let config = upgraded(json!({
+            "version": 1,
+            "ocrBounds": { "x": 10.0, "y": 20.0, "width": 300.0, "height": 200.0 },
+        }));
+        let bounds = config.ocr_bounds["Display 1920x1080@0,0"];
+        assert_eq!(
+            [bounds.x, bounds.y, bounds.width, bounds.height],
+            [10.0, 20.0, 300.0, 200.0]
+        );
+        assert_eq!(config.ocr_bounds.len(), 1);
+    }
+
+    #[test]
+    fn drops_empty_version_1_bounds() {
+        let config = upgraded(json!({
+            "version": 1,
+            "ocrBounds": { "x": 0.0, "y": 0.0, "width": 0.0, "height": 0.0 },
+        }));
+        assert!(config.ocr_bounds.is_empty());
+    }
+
+    #[test]
+    fn keeps_current_bounds_as_they_are() {
+        let config = upgraded(json!({
+            "version": CONFIG_VERSION,
+            "ocrBounds": {
+                "Side 1080x1920@1920,0": { "x": 0.0, "y": 0.0, "width": 50.0, "height": 60.0 },
+            },
+        }));
+        assert_eq!(config.ocr_bounds["Side 1080x1920@1920,0"].height, 60.0);
+    }
+}
@@ -0,0 +1,207 @@
+use crate::models::{Capture, CaptureBox, CaptureDetail, OcrResult, SavedCapture};
+use crate::state::AppState;
+use image::DynamicImage;
+use sqlx::{Pool, Sqlite};
+use std::fs;
+use std::path::PathBuf;
+use tauri::{AppHandle, Manager, State};
+
+const SELECT_CAPTURES: &str = "
+    SELECT
+        c.id, c.image_path, c.width, c.height, c.monitor_id, c.scale_factor, c.created_at,
+        COALESCE((SELECT GROUP_CONCAT(b.text, char(10)) FROM
+            (SELECT GROUP_CONCAT(text, '') AS text FROM
+                (SELECT text, block, position FROM capture_boxes
+                    WHERE capture_id = c.id ORDER BY position)
+                GROUP BY COALESCE(block, -1 - position) ORDER BY MIN(position)) b), '') AS text
+    FROM captures c";
+
+/// Screenshots are kept in `AppData/<identifier>/captures`.
+fn captures_dir(app: &AppHandle) -> crate::Result<PathBuf> {
+    let dir = app
+        .path()
+        .app_data_dir()
+        .expect("failed to get app data dir")
+        .join("captures");
+
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+    Ok(dir)
+}
+
+/// Writes the screenshot to disk and records it with its boxes.
+pub async fn save_capture(
+    app: &AppHandle,
+    image: DynamicImage,
+    result: &OcrResult,
+) -> crate::Result<SavedCapture> {
+    let file_name = format!(
+        "capture_{}.png",
+        chrono::Local::now().format("%Y%m%d_%H%M%S_%3f")
+    );
+    let image_path = captures_dir(app)?.join(file_name);
+    let (width, height) = (image.width(), image.height());
+
+    // PNG encoding of a full screen takes a while, keep it off the async workers
+    let save_path = image_path.clone();
+    tauri::async_runtime::spawn_blocking(move || image.save(save_path)).await??;
+
+    let pool = &app.state::<AppState>().db;
+    let mut tx = pool.begin().await?;
+
+    let capture_id = sqlx::query(
+        "INSERT INTO captures (image_path, width, height, monitor_id, scale_factor)
+         VALUES ($1, $2, $3, $4, $5)",
+    )
+    .bind(image_path.to_string_lossy())
+    .bind(width)
+    .bind(height)
+    .bind(result.monitor_id)
+    .bind(result.scale_factor)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    // Lines of a block are joined without a break in the transcript
+    let mut blocks = vec![None; result.boxes.len()];
+    for (index, block) in result.blocks.iter().enumerate() {
+        for &line in &block.lines {
+            blocks[line] = Some(index as i64);
+        }
+    }
+
+    let mut box_ids = Vec::with_capacity(result.boxes.len());
+    for (position, ocr_box) in result.boxes.iter().enumerate() {
+        let box_id = sqlx::query(
+            "INSERT INTO capture_boxes (capture_id, position, text, x, y, width, height, block)
+             VALUES ($1, $2, $3, $4, $5, $6, $7, $8)",
+        )
+        .bind(capture_id)
+        .bind(position as i64)
+        .bind(&ocr_box.text)
+        .bind(ocr_box.x)
+        .bind(ocr_box.y)
+        .bind(ocr_box.width)
+        .bind(ocr_box.height)
+        .bind(blocks[position])
+        .execute(&mut *tx)
+        .await?
+        .last_insert_rowid();
+        box_ids.push(box_id);
+    }
+
+    tx.commit().await?;
+    Ok(SavedCapture {
+        capture_id,
+        box_ids,
+    })
+}
+
+pub async fn fetch_capture(pool: &Pool<Sqlite>, id: i64) -> crate::Result<CaptureDetail> {
+    let capture = sqlx::query_as::<_, Capture>(&format!("{} WHERE c.id = $1", SELECT_CAPTURES))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| format!("Capture {} not found", id))?;
+
+    let boxes = sqlx::query_as::<_, CaptureBox>(
+        "SELECT id, capture_id, position, text, x, y, width, height, block
+         FROM capture_boxes WHERE capture_id = $1 ORDER BY position",
+    )
+    .bind(id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(CaptureDetail { capture, boxes })
+}
+
+#[tauri::command]
+pub async fn list_captures(state: State<'_, AppState>) -> crate::Result<Vec<Capture>> {
+    let captures = sqlx::query_as::<_, Capture>(&format!(
+        "{} ORDER BY c.created_at DESC, c.id DESC",
+        SELECT_CAPTURES
+    ))
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(captures)
+}
+
+#[tauri::command]
+pub async fn get_capture(id: i64, state: State<'_, AppState>) -> crate::Result<CaptureDetail> {
+    fetch_capture(&state.db, id).await
+}
+
+#[tauri::command]
+pub async fn delete_capture(id: i64, state: State<'_, AppState>) -> crate::Result<()> {
+    let image_path = sqlx::query_scalar::<_, String>(
+        "DELETE FROM captures WHERE id = $1 RETURNING image_path",
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await?;
+
+    if let Some(image_path) = image_path {
+        // The row is gone either way; a file removed by hand is not an error
+        if let Err(err) = fs::remove_file(&image_path) {
+            if err.kind() != std::io::ErrorKind::NotFound {
+                return Err(err.into());
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::MIGRATOR;
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    #[tokio::test]
+    async fn joins_the_lines_of_a_block_in_the_transcript() {
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        MIGRATOR.run(&pool).await.unwrap();
+
+        let capture_id = sqlx::query(
+            "INSERT INTO captures (image_path, width, height, monitor_id) VALUES ('a.png', 800, 600, 1)",
+        )
+        .execute(&pool)
+        .await
+        .unwrap()
+        .last_insert_rowid();
+        // Two blocks of two lines, then a line from a capture saved without blocks
+        let lines = [
+            ("吾輩は", Some(0)),
+            ("猫である。", Some(0)),
+            ("名前は", Some(1)),
+            ("まだ無い。", Some(1)),
+            ("どこで生れたか", None),
+        ];
+        for (position, (text, block)) in lines.into_iter().enumerate() {
+            sqlx::query(
+                "INSERT INTO capture_boxes (capture_id, position, text, x, y, width, height, block)
+                 VALUES ($1, $2, $3, 0, 0, 10, 10, $4)",
+            )
+            .bind(capture_id)
+            .bind(position as i64)
+            .bind(text)
+            .bind(block)
+            .execute(&pool)
+            .await
+            .unwrap();
+        }
+
+        let detail = fetch_capture(&pool, capture_id).await.unwrap();
+        assert_eq!(
+            detail.capture.text,
+            "吾輩は猫である。\n名前はまだ無い。\nどこで生れたか"
+        );
+        assert_eq!(detail.boxes.len(), 5);
+    }
+}
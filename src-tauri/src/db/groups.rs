@@ -0,0 +1,97 @@
+use crate::models::{NewWordGroup, WordGroup};
+use crate::state::AppState;
+use sqlx::{Pool, Sqlite};
+use tauri::State;
+
+const SELECT_GROUPS: &str = "
+    SELECT
+        g.id, g.name, g.description, g.color, g.created_at,
+        (SELECT COUNT(*) FROM word_group_members m WHERE m.group_id = g.id) AS word_count
+    FROM word_groups g";
+
+async fn fetch_group(pool: &Pool<Sqlite>, id: i64) -> crate::Result<WordGroup> {
+    let group = sqlx::query_as::<_, WordGroup>(&format!("{} WHERE g.id = $1", SELECT_GROUPS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| format!("Group {} not found", id))?;
+    Ok(group)
+}
+
+#[tauri::command]
+pub async fn get_groups(state: State<'_, AppState>) -> crate::Result<Vec<WordGroup>> {
+    let groups = sqlx::query_as::<_, WordGroup>(&format!("{} ORDER BY g.name", SELECT_GROUPS))
+        .fetch_all(&state.db)
+        .await?;
+    Ok(groups)
+}
+
+#[tauri::command]
+pub async fn add_group(
+    group: NewWordGroup,
+    state: State<'_, AppState>,
+) -> crate::Result<WordGroup> {
+    let id = sqlx::query("INSERT INTO word_groups (name, description, color) VALUES ($1, $2, $3)")
+        .bind(group.name.trim())
+        .bind(&group.description)
+        .bind(&group.color)
+        .execute(&state.db)
+        .await?
+        .last_insert_rowid();
+
+    fetch_group(&state.db, id).await
+}
+
+#[tauri::command]
+pub async fn update_group(
+    id: i64,
+    group: NewWordGroup,
+    state: State<'_, AppState>,
+) -> crate::Result<WordGroup> {
+    sqlx::query("UPDATE word_groups SET name = $1, description = $2, color = $3 WHERE id = $4")
+        .bind(group.name.trim())
+        .bind(&group.description)
+        .bind(&group.color)
+        .bind(id)
+        .execute(&state.db)
+        .await?;
+
+    fetch_group(&state.db, id).await
+}
+
+#[tauri::command]
+pub async fn delete_group(id: i64, state: State<'_, AppState>) -> crate::Result<()> {
+    sqlx::query("DELETE FROM word_groups WHERE id = $1")
+        .bind(id)
+        .execute(&state.db)
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn add_word_to_group(
+    word_id: i64,
+    group_id: i64,
+    state: State<'_, AppState>,
+) -> crate::Result<()> {
+    sqlx::query("INSERT OR IGNORE INTO word_group_members (word_id, group_id) VALUES ($1, $2)")
+        .bind(word_id)
+        .bind(group_id)
+        .execute(&state.db)
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_word_from_group(
+    word_id: i64,
+    group_id: i64,
+    state: State<'_, AppState>,
+) -> crate::Result<()> {
+    sqlx::query("DELETE FROM word_group_members WHERE word_id = $1 AND group_id = $2")
+        .bind(word_id)
+        .bind(group_id)
+        .execute(&state.db)
+        .await?;
+    Ok(())
+}
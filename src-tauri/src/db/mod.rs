@@ -0,0 +1,135 @@
+pub mod captures;
+pub mod groups;
+pub mod reviews;
+pub mod stats;
+pub mod words;
+
+use sqlx::{migrate::Migrator, sqlite::SqlitePoolOptions, Pool, Sqlite};
+use std::fs;
+use tauri::{AppHandle, Manager};
+
+/// Ordered schema migrations embedded from `src-tauri/migrations`. Applied
+/// versions are recorded in the `_sqlx_migrations` table.
+pub static MIGRATOR: Migrator = sqlx::migrate!("./migrations");
+
+pub async fn init_db(app_handle: &AppHandle) -> crate::Result<Pool<Sqlite>> {
+    // 1. Resolve the path: AppData/com.yourapp/langcapture.db
+    let app_data_dir = app_handle
+        .path()
+        .app_data_dir()
+        .expect("failed to get app data dir");
+
+    // Ensure the directory exists
+    if !app_data_dir.exists() {
+        fs::create_dir_all(&app_data_dir)?;
+    }
+
+    let db_path = app_data_dir.join("langcapture.db");
+    let db_url = format!("sqlite://{}", db_path.to_string_lossy());
+
+    // 2. Create the file if it doesn't exist (sqlx requires this step for SQLite)
+    if !std::path::Path::new(&db_path).exists() {
+        std::fs::File::create(&db_path)?;
+    }
+
+    // 3. Connect
+    let pool = SqlitePoolOptions::new()
+        .max_connections(5)
+        .connect(&db_url)
+        .await?;
+
+    // 4. Bring the schema up to date
+    MIGRATOR.run(&pool).await?;
+    println!("Database schema version: {}", schema_version(&pool).await?);
+
+    Ok(pool)
+}
+
+/// The latest migration applied to `pool`, or 0 for an unmigrated database.
+pub async fn schema_version(pool: &Pool<Sqlite>) -> crate::Result<i64> {
+    let has_migrations_table = sqlx::query_scalar::<_, bool>(
+        "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_sqlx_migrations')",
+    )
+    .fetch_one(pool)
+    .await?;
+    if !has_migrations_table {
+        return Ok(0);
+    }
+
+    let version = sqlx::query_scalar::<_, Option<i64>>(
+        "SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1",
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(version.unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A private in-memory database, kept on one connection so it outlives queries.
+    async fn memory_pool() -> Pool<Sqlite> {
+        SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap()
+    }
+
+    async fn assert_fully_migrated(pool: &Pool<Sqlite>) {
+        let applied = sqlx::query_scalar::<_, i64>(
+            "SELECT version FROM _sqlx_migrations WHERE success = 1 ORDER BY version",
+        )
+        .fetch_all(pool)
+        .await
+        .unwrap();
+        let embedded = MIGRATOR
+            .iter()
+            .map(|migration| migration.version)
+            .collect::<Vec<_>>();
+        assert_eq!(applied, embedded);
+        assert_eq!(
+            schema_version(pool).await.unwrap(),
+            *embedded.last().unwrap()
+        );
+    }
+
+    async fn has_table(pool: &Pool<Sqlite>, name: &str) -> bool {
+        sqlx::query_scalar::<_, bool>(
+            "SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = $1)",
+        )
+        .bind(name)
+        .fetch_one(pool)
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn migrates_an_empty_database() {
+        let pool = memory_pool().await;
+        assert_eq!(schema_version(&pool).await.unwrap(), 0);
+
+        MIGRATOR.run(&pool).await.unwrap();
+        assert_fully_migrated(&pool).await;
+    }
+
+    #[tokio::test]
+    async fn migrates_a_database_with_only_the_todos_schema() {
+        let pool = memory_pool().await;
+        let baseline = MIGRATOR.iter().find(|m| m.version == 1).unwrap();
+        sqlx::raw_sql(&baseline.sql).execute(&pool).await.unwrap();
+
+        sqlx::query("INSERT INTO todos (title) VALUES ('Learn kana')")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        // Databases from before migrations have the todos table but no history
+        assert_eq!(schema_version(&pool).await.unwrap(), 0);
+        MIGRATOR.run(&pool).await.unwrap();
+        assert_fully_migrated(&pool).await;
+        assert!(!has_table(&pool, "todos").await);
+        assert!(has_table(&pool, "words").await);
+    }
+}
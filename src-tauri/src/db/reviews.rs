@@ -0,0 +1,102 @@
+use super::words::SELECT_WORDS;
+use crate::models::{ReviewState, Word};
+use crate::srs::{Schedule, MAX_GRADE};
+use crate::state::AppState;
+use sqlx::{Pool, Sqlite};
+use tauri::State;
+
+/// Words due for review, oldest due first, followed by words never studied
+/// in the order they were added.
+pub async fn due_words(pool: &Pool<Sqlite>, limit: i64) -> crate::Result<Vec<Word>> {
+    let words = sqlx::query_as::<_, Word>(&format!(
+        "{} LEFT JOIN word_reviews r ON r.word_id = w.id
+        WHERE r.due_at IS NULL OR r.due_at <= datetime('now')
+        ORDER BY r.due_at IS NULL, r.due_at, w.created_at, w.id
+        LIMIT $1",
+        SELECT_WORDS
+    ))
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(words)
+}
+
+/// Schedules the next review of a word from the grade it was just given.
+pub async fn review_word(
+    pool: &Pool<Sqlite>,
+    word_id: i64,
+    grade: u8,
+) -> crate::Result<ReviewState> {
+    if grade > MAX_GRADE {
+        return Err(format!("Grade must be between 0 and {}", MAX_GRADE).into());
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let exists = sqlx::query_scalar::<_, bool>("SELECT EXISTS (SELECT 1 FROM words WHERE id = $1)")
+        .bind(word_id)
+        .fetch_one(&mut *tx)
+        .await?;
+    if !exists {
+        return Err(format!("Word {} not found", word_id).into());
+    }
+
+    let current = sqlx::query_as::<_, (f64, i64, i64)>(
+        "SELECT ease, interval_days, repetitions FROM word_reviews WHERE word_id = $1",
+    )
+    .bind(word_id)
+    .fetch_optional(&mut *tx)
+    .await?;
+    let schedule = current
+        .map(|(ease, interval_days, repetitions)| Schedule {
+            ease,
+            interval_days,
+            repetitions,
+        })
+        .unwrap_or_default()
+        .review(grade);
+
+    let review = sqlx::query_as::<_, ReviewState>(
+        "INSERT INTO word_reviews (word_id, ease, interval_days, repetitions, due_at)
+         VALUES ($1, $2, $3, $4, datetime('now', '+' || $3 || ' days'))
+         ON CONFLICT (word_id) DO UPDATE SET
+            ease = excluded.ease,
+            interval_days = excluded.interval_days,
+            repetitions = excluded.repetitions,
+            due_at = excluded.due_at
+         RETURNING word_id, ease, interval_days, repetitions, due_at",
+    )
+    .bind(word_id)
+    .bind(schedule.ease)
+    .bind(schedule.interval_days)
+    .bind(schedule.repetitions)
+    .fetch_one(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        "INSERT INTO review_log (word_id, grade, ease, interval_days) VALUES ($1, $2, $3, $4)",
+    )
+    .bind(word_id)
+    .bind(grade)
+    .bind(schedule.ease)
+    .bind(schedule.interval_days)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(review)
+}
+
+#[tauri::command]
+pub async fn get_due_cards(limit: i64, state: State<'_, AppState>) -> crate::Result<Vec<Word>> {
+    due_words(&state.db, limit).await
+}
+
+#[tauri::command]
+pub async fn submit_review(
+    word_id: i64,
+    grade: u8,
+    state: State<'_, AppState>,
+) -> crate::Result<ReviewState> {
+    review_word(&state.db, word_id, grade).await
+}
@@ -0,0 +1,80 @@
+use crate::models::{DailyActivity, UserStats};
+use crate::state::AppState;
+use chrono::{Datelike, Duration, Local, NaiveDate};
+use sqlx::{Pool, Sqlite};
+use std::collections::HashMap;
+use tauri::State;
+
+/// Days covered by the activity histogram, ending today.
+const ACTIVITY_DAYS: i64 = 7;
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Timestamps are stored in UTC, so every date below goes through
+/// `'localtime'` before being compared with `today`.
+pub async fn user_stats(pool: &Pool<Sqlite>, today: NaiveDate) -> crate::Result<UserStats> {
+    let week_start = today - Duration::days(today.weekday().num_days_from_monday().into());
+    let month_start = today.with_day(1).unwrap_or(today);
+    let activity_start = today - Duration::days(ACTIVITY_DAYS - 1);
+
+    let mut stats = sqlx::query_as::<_, UserStats>(
+        "SELECT
+            (SELECT COUNT(*) FROM words) AS total_words,
+            (SELECT COUNT(*) FROM words
+                WHERE date(created_at, 'localtime') >= $1) AS words_this_week,
+            (SELECT COUNT(*) FROM words
+                WHERE date(created_at, 'localtime') >= $2) AS words_this_month,
+            (SELECT COUNT(*) FROM words WHERE is_favorite = 1) AS favorite_count,
+            (SELECT COUNT(*) FROM word_groups) AS group_count,
+            (SELECT COUNT(DISTINCT date(reviewed_at, 'localtime')) FROM review_log)
+                AS study_sessions,
+            (SELECT datetime(MAX(reviewed_at), 'localtime') FROM review_log) AS last_study_date",
+    )
+    .bind(week_start.format(DATE_FORMAT).to_string())
+    .bind(month_start.format(DATE_FORMAT).to_string())
+    .fetch_one(pool)
+    .await?;
+
+    let start = activity_start.format(DATE_FORMAT).to_string();
+    let words_added = count_by_day(pool, "words", "created_at", &start).await?;
+    let reviews = count_by_day(pool, "review_log", "reviewed_at", &start).await?;
+
+    // Days without any activity are still listed, with zero counts
+    stats.daily = (0..ACTIVITY_DAYS)
+        .map(|offset| {
+            let date = (activity_start + Duration::days(offset))
+                .format(DATE_FORMAT)
+                .to_string();
+            DailyActivity {
+                words_added: words_added.get(&date).copied().unwrap_or(0),
+                reviews: reviews.get(&date).copied().unwrap_or(0),
+                date,
+            }
+        })
+        .collect();
+
+    Ok(stats)
+}
+
+/// Rows of `table` per local date of `column`, from `start` onwards.
+async fn count_by_day(
+    pool: &Pool<Sqlite>,
+    table: &str,
+    column: &str,
+    start: &str,
+) -> crate::Result<HashMap<String, i64>> {
+    let counts = sqlx::query_as::<_, (String, i64)>(&format!(
+        "SELECT date({column}, 'localtime') AS day, COUNT(*) FROM {table}
+         WHERE date({column}, 'localtime') >= $1
+         GROUP BY day",
+    ))
+    .bind(start)
+    .fetch_all(pool)
+    .await?;
+    Ok(counts.into_iter().collect())
+}
+
+#[tauri::command]
+pub async fn get_user_stats(state: State<'_, AppState>) -> crate::Result<UserStats> {
+    user_stats(&state.db, Local::now().date_naive()).await
+}
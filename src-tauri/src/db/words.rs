@@ -0,0 +1,248 @@
+use crate::models::{NewWord, Tag, Word, WordFilter};
+use crate::state::AppState;
+use sqlx::{types::Json, Pool, Sqlite, SqliteConnection};
+use tauri::State;
+
+// Group ids and tag names are aggregated into JSON arrays so a word maps onto a single row
+pub(super) const SELECT_WORDS: &str = "
+    SELECT
+        w.id, w.word, w.meaning, w.screenshot, w.translated_text, w.source_language,
+        w.target_language, w.created_at, w.is_favorite, w.pronunciation, w.examples, w.notes,
+        w.source_box_id,
+        (SELECT json_group_array(m.group_id) FROM word_group_members m
+            WHERE m.word_id = w.id) AS group_ids,
+        (SELECT json_group_array(t.name) FROM word_tags wt JOIN tags t ON t.id = wt.tag_id
+            WHERE wt.word_id = w.id) AS tags
+    FROM words w";
+
+pub async fn fetch_word(pool: &Pool<Sqlite>, id: i64) -> crate::Result<Word> {
+    let word = sqlx::query_as::<_, Word>(&format!("{} WHERE w.id = $1", SELECT_WORDS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await?
+        .ok_or_else(|| format!("Word {} not found", id))?;
+    Ok(word)
+}
+
+pub async fn fetch_words(pool: &Pool<Sqlite>, filter: &WordFilter) -> crate::Result<Vec<Word>> {
+    let query = filter
+        .query
+        .as_deref()
+        .map(str::trim)
+        .filter(|query| !query.is_empty())
+        .map(|query| format!("%{}%", query));
+
+    let words = sqlx::query_as::<_, Word>(&format!(
+        "{} WHERE ($1 IS NULL
+                OR w.word LIKE $1 OR w.meaning LIKE $1 OR w.translated_text LIKE $1
+                OR w.pronunciation LIKE $1 OR w.notes LIKE $1)
+            AND ($2 = 0 OR w.is_favorite = 1)
+            AND ($3 IS NULL OR EXISTS (
+                SELECT 1 FROM word_group_members m WHERE m.word_id = w.id AND m.group_id = $3))
+            AND ($4 IS NULL OR EXISTS (
+                SELECT 1 FROM word_tags wt JOIN tags t ON t.id = wt.tag_id
+                WHERE wt.word_id = w.id AND t.name = $4))
+        ORDER BY w.created_at DESC, w.id DESC",
+        SELECT_WORDS
+    ))
+    .bind(query)
+    .bind(filter.favorites_only)
+    .bind(filter.group_id)
+    .bind(filter.tag.as_deref())
+    .fetch_all(pool)
+    .await?;
+
+    Ok(words)
+}
+
+/// Replaces the tags of a word, creating any tag that does not exist yet.
+async fn replace_tags(
+    conn: &mut SqliteConnection,
+    word_id: i64,
+    tags: &[String],
+) -> crate::Result<()> {
+    sqlx::query("DELETE FROM word_tags WHERE word_id = $1")
+        .bind(word_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for tag in tags.iter().map(|tag| tag.trim()).filter(|tag| !tag.is_empty()) {
+        sqlx::query("INSERT OR IGNORE INTO tags (name) VALUES ($1)")
+            .bind(tag)
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query(
+            "INSERT OR IGNORE INTO word_tags (word_id, tag_id)
+             SELECT $1, id FROM tags WHERE name = $2",
+        )
+        .bind(word_id)
+        .bind(tag)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+async fn replace_groups(
+    conn: &mut SqliteConnection,
+    word_id: i64,
+    group_ids: &[i64],
+) -> crate::Result<()> {
+    sqlx::query("DELETE FROM word_group_members WHERE word_id = $1")
+        .bind(word_id)
+        .execute(&mut *conn)
+        .await?;
+
+    for group_id in group_ids {
+        sqlx::query("INSERT OR IGNORE INTO word_group_members (word_id, group_id) VALUES ($1, $2)")
+            .bind(word_id)
+            .bind(group_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    Ok(())
+}
+
+pub async fn insert_word(pool: &Pool<Sqlite>, word: &NewWord) -> crate::Result<i64> {
+    let mut tx = pool.begin().await?;
+
+    let id = sqlx::query(
+        "INSERT INTO words (word, meaning, screenshot, translated_text, source_language,
+            target_language, pronunciation, examples, notes, is_favorite, source_box_id)
+         VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)",
+    )
+    .bind(&word.word)
+    .bind(&word.meaning)
+    .bind(&word.screenshot)
+    .bind(&word.translated_text)
+    .bind(&word.source_language)
+    .bind(&word.target_language)
+    .bind(&word.pronunciation)
+    .bind(Json(&word.examples))
+    .bind(&word.notes)
+    .bind(word.is_favorite)
+    .bind(word.source_box_id)
+    .execute(&mut *tx)
+    .await?
+    .last_insert_rowid();
+
+    replace_groups(&mut tx, id, &word.group_ids).await?;
+    replace_tags(&mut tx, id, &word.tags).await?;
+    tx.commit().await?;
+
+    Ok(id)
+}
+
+#[tauri::command]
+pub async fn get_words(state: State<'_, AppState>) -> crate::Result<Vec<Word>> {
+    fetch_words(&state.db, &WordFilter::default()).await
+}
+
+#[tauri::command]
+pub async fn search_words(
+    filter: WordFilter,
+    state: State<'_, AppState>,
+) -> crate::Result<Vec<Word>> {
+    fetch_words(&state.db, &filter).await
+}
+
+#[tauri::command]
+pub async fn get_word(id: i64, state: State<'_, AppState>) -> crate::Result<Word> {
+    fetch_word(&state.db, id).await
+}
+
+#[tauri::command]
+pub async fn add_word(word: NewWord, state: State<'_, AppState>) -> crate::Result<Word> {
+    let id = insert_word(&state.db, &word).await?;
+    fetch_word(&state.db, id).await
+}
+
+#[tauri::command]
+pub async fn update_word(
+    id: i64,
+    word: NewWord,
+    state: State<'_, AppState>,
+) -> crate::Result<Word> {
+    let mut tx = state.db.begin().await?;
+
+    let updated = sqlx::query(
+        "UPDATE words SET word = $1, meaning = $2, screenshot = $3, translated_text = $4,
+            source_language = $5, target_language = $6, pronunciation = $7, examples = $8,
+            notes = $9, is_favorite = $10, source_box_id = $11
+         WHERE id = $12",
+    )
+    .bind(&word.word)
+    .bind(&word.meaning)
+    .bind(&word.screenshot)
+    .bind(&word.translated_text)
+    .bind(&word.source_language)
+    .bind(&word.target_language)
+    .bind(&word.pronunciation)
+    .bind(Json(&word.examples))
+    .bind(&word.notes)
+    .bind(word.is_favorite)
+    .bind(word.source_box_id)
+    .bind(id)
+    .execute(&mut *tx)
+    .await?
+    .rows_affected();
+
+    if updated == 0 {
+        return Err(format!("Word {} not found", id).into());
+    }
+
+    replace_groups(&mut tx, id, &word.group_ids).await?;
+    replace_tags(&mut tx, id, &word.tags).await?;
+    tx.commit().await?;
+
+    fetch_word(&state.db, id).await
+}
+
+#[tauri::command]
+pub async fn delete_word(id: i64, state: State<'_, AppState>) -> crate::Result<()> {
+    sqlx::query("DELETE FROM words WHERE id = $1")
+        .bind(id)
+        .execute(&state.db)
+        .await?;
+    Ok(())
+}
+
+/// Flips the favorite flag and returns the new value.
+#[tauri::command]
+pub async fn toggle_favorite(id: i64, state: State<'_, AppState>) -> crate::Result<bool> {
+    let is_favorite = sqlx::query_scalar::<_, bool>(
+        "UPDATE words SET is_favorite = NOT is_favorite WHERE id = $1 RETURNING is_favorite",
+    )
+    .bind(id)
+    .fetch_optional(&state.db)
+    .await?
+    .ok_or_else(|| format!("Word {} not found", id))?;
+
+    Ok(is_favorite)
+}
+
+#[tauri::command]
+pub async fn get_tags(state: State<'_, AppState>) -> crate::Result<Vec<Tag>> {
+    let tags = sqlx::query_as::<_, Tag>(
+        "SELECT t.id, t.name, COUNT(wt.word_id) AS word_count
+         FROM tags t LEFT JOIN word_tags wt ON wt.tag_id = t.id
+         GROUP BY t.id ORDER BY t.name",
+    )
+    .fetch_all(&state.db)
+    .await?;
+
+    Ok(tags)
+}
+
+#[tauri::command]
+pub async fn set_word_tags(
+    word_id: i64,
+    tags: Vec<String>,
+    state: State<'_, AppState>,
+) -> crate::Result<Word> {
+    let mut tx = state.db.begin().await?;
+    replace_tags(&mut tx, word_id, &tags).await?;
+    tx.commit().await?;
+
+    fetch_word(&state.db, word_id).await
+}
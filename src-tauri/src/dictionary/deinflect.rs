@@ -0,0 +1,416 @@
+//! Rule based deinflection in the style of Yomichan: conjugated text is
+//! repeatedly stripped of known endings to produce candidate dictionary forms,
+//! together with the chain of inflections that was undone.
+
+use std::collections::HashSet;
+use std::sync::OnceLock;
+
+// Word classes a candidate can belong to, as bit flags
+pub const V1: u32 = 1 << 0;
+pub const V5: u32 = 1 << 1;
+pub const VK: u32 = 1 << 2;
+pub const VS: u32 = 1 << 3;
+pub const ADJ_I: u32 = 1 << 4;
+pub const ADJ_NA: u32 = 1 << 5;
+/// A te-form left by undoing ている, てしまう, ...
+const TE: u32 = 1 << 6;
+/// Only the text as it was looked up, for endings nothing else conjugates into.
+const INPUT: u32 = 1 << 7;
+/// The looked up text itself, which may be anything.
+pub const ANY: u32 = u32::MAX;
+
+const VERB: u32 = V1 | V5 | VK | VS;
+
+/// Maps a JMdict part-of-speech code onto the word class flags.
+pub fn part_of_speech_flags(code: &str) -> u32 {
+    match code {
+        "v1" | "v1-s" => V1,
+        "vk" => VK,
+        "vs" | "vs-i" | "vs-s" | "vz" => VS,
+        "adj-i" | "adj-ix" => ADJ_I,
+        "adj-na" => ADJ_NA,
+        code if code.starts_with("v5") => V5,
+        _ => 0,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Deinflection {
+    pub term: String,
+    /// Word classes the term must belong to; [`ANY`] when nothing was undone.
+    pub conditions: u32,
+    /// Undone inflections, from the dictionary form outwards.
+    pub reasons: Vec<&'static str>,
+}
+
+struct Rule {
+    reason: &'static str,
+    ending: String,
+    replacement: String,
+    conditions_in: u32,
+    conditions_out: u32,
+}
+
+/// Godan dictionary endings with their i, a, e and o row kana, and te/ta forms.
+const GODAN: [(&str, &str, &str, &str, &str, &str, &str); 9] = [
+    ("う", "い", "わ", "え", "お", "って", "った"),
+    ("く", "き", "か", "け", "こ", "いて", "いた"),
+    ("ぐ", "ぎ", "が", "げ", "ご", "いで", "いだ"),
+    ("す", "し", "さ", "せ", "そ", "して", "した"),
+    ("つ", "ち", "た", "て", "と", "って", "った"),
+    ("ぬ", "に", "な", "ね", "の", "んで", "んだ"),
+    ("ぶ", "び", "ば", "べ", "ぼ", "んで", "んだ"),
+    ("む", "み", "ま", "め", "も", "んで", "んだ"),
+    ("る", "り", "ら", "れ", "ろ", "って", "った"),
+];
+
+/// 来る is written both in kana and with its kanji.
+const KURU: [(&str, &str, &str); 2] = [("くる", "き", "こ"), ("来る", "来", "来")];
+
+#[derive(Default)]
+struct RuleTable(Vec<Rule>);
+
+impl RuleTable {
+    fn add(
+        &mut self,
+        reason: &'static str,
+        ending: &str,
+        replacement: &str,
+        conditions_in: u32,
+        conditions_out: u32,
+    ) {
+        self.0.push(Rule {
+            reason,
+            ending: ending.to_string(),
+            replacement: replacement.to_string(),
+            conditions_in,
+            conditions_out,
+        });
+    }
+
+    /// `suffix` attached to the continuative (masu) stem of every verb class.
+    fn masu_stem(&mut self, reason: &'static str, suffix: &str, conditions_in: u32) {
+        self.add(reason, suffix, "る", conditions_in, V1);
+        for (u, i, ..) in GODAN {
+            self.add(reason, &format!("{i}{suffix}"), u, conditions_in, V5);
+        }
+        for (kuru, ki, _) in KURU {
+            self.add(reason, &format!("{ki}{suffix}"), kuru, conditions_in, VK);
+        }
+        self.add(reason, &format!("し{suffix}"), "する", conditions_in, VS);
+    }
+
+    /// `suffix` attached to the negative (a-row) stem of every verb class.
+    fn negative_stem(&mut self, reason: &'static str, suffix: &str, conditions_in: u32) {
+        self.add(reason, suffix, "る", conditions_in, V1);
+        for (u, _, a, ..) in GODAN {
+            self.add(reason, &format!("{a}{suffix}"), u, conditions_in, V5);
+        }
+        for (kuru, _, ko) in KURU {
+            self.add(reason, &format!("{ko}{suffix}"), kuru, conditions_in, VK);
+        }
+        self.add(reason, &format!("し{suffix}"), "する", conditions_in, VS);
+    }
+
+    /// The te or ta form of every verb class followed by `suffix`.
+    fn te_form(&mut self, reason: &'static str, past: bool, suffix: &str, conditions_in: u32) {
+        let te = if past { "た" } else { "て" };
+        self.add(reason, &format!("{te}{suffix}"), "る", conditions_in, V1);
+        for (u, _, _, _, _, te_form, ta_form) in GODAN {
+            let form = if past { ta_form } else { te_form };
+            self.add(reason, &format!("{form}{suffix}"), u, conditions_in, V5);
+        }
+        // 行く is the one く verb with a って form
+        for (iku, stem) in [("いく", "い"), ("行く", "行")] {
+            let form = if past { "った" } else { "って" };
+            self.add(
+                reason,
+                &format!("{stem}{form}{suffix}"),
+                iku,
+                conditions_in,
+                V5,
+            );
+        }
+        for (kuru, ki, _) in KURU {
+            self.add(
+                reason,
+                &format!("{ki}{te}{suffix}"),
+                kuru,
+                conditions_in,
+                VK,
+            );
+        }
+        self.add(
+            reason,
+            &format!("し{te}{suffix}"),
+            "する",
+            conditions_in,
+            VS,
+        );
+    }
+
+    /// An i-adjective ending replacing the final い.
+    fn adjective(
+        &mut self,
+        reason: &'static str,
+        ending: &str,
+        conditions_in: u32,
+        conditions_out: u32,
+    ) {
+        self.add(reason, ending, "い", conditions_in, conditions_out);
+        // いい conjugates from its older form よい
+        self.add(
+            reason,
+            &format!("よ{ending}"),
+            "いい",
+            conditions_in,
+            conditions_out,
+        );
+    }
+}
+
+fn rules() -> &'static [Rule] {
+    static RULES: OnceLock<Vec<Rule>> = OnceLock::new();
+    RULES.get_or_init(|| {
+        let mut table = RuleTable::default();
+
+        // Polite forms
+        table.masu_stem("polite", "ます", INPUT);
+        table.masu_stem("polite negative", "ません", INPUT);
+        table.masu_stem("polite past", "ました", INPUT);
+        table.masu_stem("polite past negative", "ませんでした", INPUT);
+        table.masu_stem("polite volitional", "ましょう", INPUT);
+        table.masu_stem("polite te", "まして", INPUT);
+
+        // Auxiliaries on the masu stem
+        table.masu_stem("-tai", "たい", ADJ_I);
+        table.masu_stem("-sou", "そう", INPUT);
+        table.masu_stem("-sugiru", "すぎる", V1);
+        table.masu_stem("-nagara", "ながら", INPUT);
+        table.masu_stem("-nasai", "なさい", INPUT);
+
+        // Negatives, ない conjugating further as an i-adjective
+        table.negative_stem("negative", "ない", ADJ_I);
+        table.negative_stem("-zu", "ず", INPUT);
+        table.negative_stem("-zu", "ずに", INPUT);
+        table.negative_stem("-nu", "ぬ", INPUT);
+
+        // Te and ta forms
+        table.te_form("-te", false, "", INPUT | TE);
+        table.te_form("past", true, "", INPUT);
+        table.te_form("-tara", true, "ら", INPUT);
+        table.te_form("-tari", true, "り", INPUT);
+
+        // Auxiliaries on the te form, undone back to it
+        for (reason, te_ending, de_ending, conditions_in) in [
+            ("progressive or perfect", "ている", "でいる", V1),
+            ("progressive or perfect", "てる", "でる", V1),
+            ("-shimau", "てしまう", "でしまう", V5),
+            ("-kudasai", "てください", "でください", INPUT),
+            ("-oku", "ておく", "でおく", V5),
+            ("-oku", "とく", "どく", V5),
+            ("-iku", "ていく", "でいく", V5),
+            ("-kuru", "てくる", "でくる", VK),
+        ] {
+            table.add(reason, te_ending, "て", conditions_in, TE);
+            table.add(reason, de_ending, "で", conditions_in, TE);
+        }
+        // てしまう contracts to ちゃう
+        table.add("-chau", "ちゃう", "て", V5, TE);
+        table.add("-chau", "じゃう", "で", V5, TE);
+
+        // Passive, causative and potential, all conjugating as ichidan verbs
+        table.add("potential or passive", "られる", "る", V1, V1);
+        for (u, _, a, e, ..) in GODAN {
+            table.add("passive", &format!("{a}れる"), u, V1, V5);
+            table.add("causative", &format!("{a}せる"), u, V1, V5);
+            table.add("potential", &format!("{e}る"), u, V1, V5);
+        }
+        table.add("causative", "させる", "る", V1, V1);
+        for (kuru, _, ko) in KURU {
+            table.add("potential or passive", &format!("{ko}られる"), kuru, V1, VK);
+            table.add("causative", &format!("{ko}させる"), kuru, V1, VK);
+        }
+        table.add("passive", "される", "する", V1, VS);
+        table.add("causative", "させる", "する", V1, VS);
+        table.add("potential", "できる", "する", V1, VS);
+
+        // Conditional, volitional and imperative
+        table.add("-ba", "れば", "る", INPUT, V1);
+        for (u, _, _, e, o, ..) in GODAN {
+            table.add("-ba", &format!("{e}ば"), u, INPUT, V5);
+            table.add("volitional", &format!("{o}う"), u, INPUT, V5);
+            table.add("imperative", e, u, INPUT, V5);
+        }
+        table.add("volitional", "よう", "る", INPUT, V1);
+        table.add("imperative", "ろ", "る", INPUT, V1);
+        table.add("imperative", "よ", "る", INPUT, V1);
+        for (kuru, _, ko) in KURU {
+            table.add(
+                "-ba",
+                &format!("{}れば", kuru.trim_end_matches('る')),
+                kuru,
+                INPUT,
+                VK,
+            );
+            table.add("volitional", &format!("{ko}よう"), kuru, INPUT, VK);
+            table.add("imperative", &format!("{ko}い"), kuru, INPUT, VK);
+        }
+        table.add("-ba", "すれば", "する", INPUT, VS);
+        table.add("volitional", "しよう", "する", INPUT, VS);
+        table.add("imperative", "しろ", "する", INPUT, VS);
+        table.add("imperative", "せよ", "する", INPUT, VS);
+        table.add("imperative negative", "な", "", INPUT, VERB);
+
+        // Nouns taking する
+        table.add("-suru", "する", "", VS, VS);
+
+        // i-adjectives
+        table.adjective("adv", "く", INPUT, ADJ_I);
+        table.adjective("-te", "くて", INPUT, ADJ_I);
+        table.adjective("past", "かった", INPUT, ADJ_I);
+        table.adjective("negative", "くない", ADJ_I, ADJ_I);
+        table.adjective("-ba", "ければ", INPUT, ADJ_I);
+        table.adjective("-tara", "かったら", INPUT, ADJ_I);
+        table.adjective("-tari", "かったり", INPUT, ADJ_I);
+        table.adjective("noun", "さ", INPUT, ADJ_I);
+        table.adjective("-sou", "そう", INPUT, ADJ_I);
+        table.adjective("-sugiru", "すぎる", V1, ADJ_I);
+        table.adjective("polite negative", "くありません", INPUT, ADJ_I);
+
+        // na-adjectives and the copula
+        for (reason, ending, conditions_in) in [
+            ("copula", "だ", INPUT),
+            ("copula", "です", INPUT),
+            ("past", "だった", INPUT),
+            ("polite past", "でした", INPUT),
+            ("attributive", "な", INPUT),
+            ("adv", "に", INPUT),
+            ("-te", "で", INPUT),
+            ("negative", "じゃない", ADJ_I),
+            ("negative", "ではない", ADJ_I),
+        ] {
+            table.add(reason, ending, "", conditions_in, ADJ_NA);
+        }
+
+        table.0
+    })
+}
+
+/// Every candidate dictionary form of `source`, starting with `source` itself.
+pub fn deinflect(source: &str) -> Vec<Deinflection> {
+    let mut results = vec![Deinflection {
+        term: source.to_string(),
+        conditions: ANY,
+        reasons: Vec::new(),
+    }];
+    let mut seen = HashSet::new();
+    seen.insert((source.to_string(), ANY));
+
+    let mut index = 0;
+    while index < results.len() {
+        for rule in rules() {
+            let current = &results[index];
+            if current.conditions & rule.conditions_in == 0 {
+                continue;
+            }
+            let Some(stem) = current.term.strip_suffix(rule.ending.as_str()) else {
+                continue;
+            };
+            if stem.is_empty() && rule.replacement.is_empty() {
+                continue;
+            }
+
+            let term = format!("{}{}", stem, rule.replacement);
+            if !seen.insert((term.clone(), rule.conditions_out)) {
+                continue;
+            }
+            let mut reasons = vec![rule.reason];
+            reasons.extend(&current.reasons);
+            results.push(Deinflection {
+                term,
+                conditions: rule.conditions_out,
+                reasons,
+            });
+        }
+        index += 1;
+    }
+    results
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The reasons undone to reach `term` as a word of `class`, if it is a candidate.
+    fn reasons(source: &str, term: &str, class: u32) -> Option<Vec<&'static str>> {
+        deinflect(source)
+            .into_iter()
+            .find(|d| d.term == term && d.conditions & class != 0)
+            .map(|d| d.reasons)
+    }
+
+    #[test]
+    fn keeps_the_source_as_the_first_candidate() {
+        let candidates = deinflect("食べる");
+        assert_eq!(candidates[0].term, "食べる");
+        assert_eq!(candidates[0].conditions, ANY);
+        assert!(candidates[0].reasons.is_empty());
+    }
+
+    #[test]
+    fn undoes_a_chain_of_inflections() {
+        assert_eq!(
+            reasons("食べられなかった", "食べる", V1),
+            Some(vec!["potential or passive", "negative", "past"])
+        );
+    }
+
+    #[test]
+    fn strips_the_copula_from_na_adjectives() {
+        assert_eq!(reasons("静かで", "静か", ADJ_NA), Some(vec!["-te"]));
+    }
+
+    #[test]
+    fn undoes_godan_past_forms() {
+        assert_eq!(reasons("書いた", "書く", V5), Some(vec!["past"]));
+        assert_eq!(reasons("飲んだ", "飲む", V5), Some(vec!["past"]));
+        assert_eq!(reasons("行った", "行く", V5), Some(vec!["past"]));
+    }
+
+    #[test]
+    fn undoes_auxiliaries_on_the_te_form() {
+        assert_eq!(
+            reasons("読んでいる", "読む", V5),
+            Some(vec!["-te", "progressive or perfect"])
+        );
+        assert_eq!(
+            reasons("食べてしまった", "食べる", V1),
+            Some(vec!["-te", "-shimau", "past"])
+        );
+    }
+
+    #[test]
+    fn undoes_i_adjective_negatives() {
+        assert_eq!(reasons("高くない", "高い", ADJ_I), Some(vec!["negative"]));
+        assert_eq!(
+            reasons("高くなかった", "高い", ADJ_I),
+            Some(vec!["negative", "past"])
+        );
+        assert_eq!(reasons("よくない", "いい", ADJ_I), Some(vec!["negative"]));
+    }
+
+    #[test]
+    fn rejects_rules_for_the_wrong_word_class() {
+        // 書いて alone may be the imperative of a made up つ verb...
+        assert!(reasons("書いて", "書いつ", V5).is_some());
+        // ...but a te-form left by undoing ている only takes te-form rules
+        assert!(reasons("書いている", "書いつ", V5).is_none());
+        assert!(reasons("書いている", "書く", V5).is_some());
+
+        // The godan reading of 食べられる does not fit the ichidan 食べる
+        let godan = reasons("食べられなかった", "食べる", V5);
+        assert_eq!(godan, Some(vec!["passive", "negative", "past"]));
+        assert_eq!(part_of_speech_flags("v1") & V5, 0);
+    }
+}
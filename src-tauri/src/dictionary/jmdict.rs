@@ -0,0 +1,136 @@
+//! Streaming reader for the JMdict XML release (`JMdict_e`, optionally gzipped).
+
+use crate::models::{DictionaryEntry, KanjiForm, ReadingForm, Sense};
+use crate::Result;
+use quick_xml::escape::resolve_xml_entity;
+use quick_xml::events::{BytesRef, BytesStart, Event};
+use quick_xml::Reader;
+use std::io::BufRead;
+
+pub struct JmdictReader<R: BufRead> {
+    reader: Reader<R>,
+    buf: Vec<u8>,
+}
+
+impl<R: BufRead> JmdictReader<R> {
+    pub fn new(source: R) -> Self {
+        Self {
+            reader: Reader::from_reader(source),
+            buf: Vec::new(),
+        }
+    }
+
+    /// Reads the next `<entry>`, or `None` once the file is exhausted.
+    pub fn next_entry(&mut self) -> Result<Option<DictionaryEntry>> {
+        let mut entry: Option<DictionaryEntry> = None;
+        let mut text = String::new();
+        let mut keep_gloss = true;
+
+        loop {
+            self.buf.clear();
+            match self.reader.read_event_into(&mut self.buf)? {
+                Event::Start(start) => {
+                    text.clear();
+                    match (start.local_name().as_ref(), entry.as_mut()) {
+                        (b"entry", None) => entry = Some(DictionaryEntry::default()),
+                        (b"k_ele", Some(current)) => current.kanji.push(KanjiForm::default()),
+                        (b"r_ele", Some(current)) => current.readings.push(ReadingForm::default()),
+                        (b"sense", Some(current)) => current.senses.push(Sense::default()),
+                        (b"gloss", Some(_)) => keep_gloss = is_english(&start),
+                        _ => {}
+                    }
+                }
+                Event::Empty(empty) => {
+                    if let (b"re_nokanji", Some(current)) =
+                        (empty.local_name().as_ref(), entry.as_mut())
+                    {
+                        with_last(&mut current.readings, |r| r.no_kanji = true);
+                    }
+                }
+                Event::Text(content) => {
+                    text.push_str(&content.decode().map_err(quick_xml::Error::from)?)
+                }
+                Event::GeneralRef(reference) => push_reference(&mut text, &reference)?,
+                Event::End(end) => {
+                    let Some(current) = entry.as_mut() else {
+                        continue;
+                    };
+                    // Not trimmed by the reader, which would also eat the spaces around `&amp;`
+                    let value = std::mem::take(&mut text).trim().to_string();
+                    match end.local_name().as_ref() {
+                        b"ent_seq" => current.id = value.parse().unwrap_or_default(),
+                        b"keb" => with_last(&mut current.kanji, |k| k.text = value),
+                        b"ke_inf" => with_last(&mut current.kanji, |k| k.info.push(value)),
+                        b"ke_pri" => with_last(&mut current.kanji, |k| k.priorities.push(value)),
+                        b"reb" => with_last(&mut current.readings, |r| r.text = value),
+                        b"re_nokanji" => with_last(&mut current.readings, |r| r.no_kanji = true),
+                        b"re_restr" => {
+                            with_last(&mut current.readings, |r| r.restrictions.push(value))
+                        }
+                        b"re_inf" => with_last(&mut current.readings, |r| r.info.push(value)),
+                        b"re_pri" => with_last(&mut current.readings, |r| r.priorities.push(value)),
+                        b"pos" => with_last(&mut current.senses, |s| s.parts_of_speech.push(value)),
+                        b"field" => with_last(&mut current.senses, |s| s.fields.push(value)),
+                        b"misc" => with_last(&mut current.senses, |s| s.misc.push(value)),
+                        b"dial" => with_last(&mut current.senses, |s| s.dialects.push(value)),
+                        b"s_inf" => with_last(&mut current.senses, |s| s.info.push(value)),
+                        b"gloss" if keep_gloss => {
+                            with_last(&mut current.senses, |s| s.glosses.push(value))
+                        }
+                        b"sense" => inherit_parts_of_speech(&mut current.senses),
+                        b"entry" => return Ok(entry),
+                        _ => {}
+                    }
+                }
+                Event::Eof => return Ok(None),
+                _ => {}
+            }
+        }
+    }
+}
+
+impl<R: BufRead> Iterator for JmdictReader<R> {
+    type Item = Result<DictionaryEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_entry().transpose()
+    }
+}
+
+fn with_last<T>(items: &mut [T], update: impl FnOnce(&mut T)) {
+    if let Some(item) = items.last_mut() {
+        update(item);
+    }
+}
+
+/// Glosses without `xml:lang` are English; other languages only appear in the full JMdict.
+fn is_english(start: &BytesStart) -> bool {
+    match start.try_get_attribute("xml:lang") {
+        Ok(Some(lang)) => &*lang.value == b"eng",
+        _ => true,
+    }
+}
+
+/// JMdict declares its tags (`&v1;`, `&n;`, ...) as DTD entities. The entity
+/// name is the short code, which is what we keep instead of the long description.
+pub(crate) fn push_reference(text: &mut String, reference: &BytesRef) -> Result<()> {
+    if let Some(ch) = reference.resolve_char_ref()? {
+        text.push(ch);
+        return Ok(());
+    }
+    let name = reference.decode().map_err(quick_xml::Error::from)?;
+    match resolve_xml_entity(&name) {
+        Some(value) => text.push_str(value),
+        None => text.push_str(&name),
+    }
+    Ok(())
+}
+
+/// A sense without `<pos>` uses the parts of speech of the sense before it.
+fn inherit_parts_of_speech(senses: &mut [Sense]) {
+    if let [.., previous, last] = senses {
+        if last.parts_of_speech.is_empty() {
+            last.parts_of_speech = previous.parts_of_speech.clone();
+        }
+    }
+}
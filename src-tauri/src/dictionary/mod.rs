@@ -0,0 +1,329 @@
+pub mod deinflect;
+pub mod jmdict;
+pub mod yomitan;
+
+use crate::models::{DictionaryEntry, DictionaryMatch, DictionaryStatus};
+use crate::state::AppState;
+use crate::Result;
+use deinflect::{deinflect, part_of_speech_flags, Deinflection, ANY};
+use flate2::read::GzDecoder;
+use jmdict::JmdictReader;
+use sqlx::{types::Json, Pool, Sqlite, SqliteConnection};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+use tokio::sync::mpsc;
+
+/// Files looked for in `resources/jmdict` on first start, in order of preference.
+const BUNDLED_DICTIONARIES: [&str; 4] = ["JMdict_e.gz", "JMdict_e", "JMdict_e.xml", "jmdict.zip"];
+
+/// Entries written per transaction, so captures and words can still be saved during an import.
+const IMPORT_BATCH_SIZE: usize = 1000;
+
+/// Longest prefix of the looked up text that is deinflected and tried against the dictionary forms.
+const MAX_LOOKUP_LENGTH: usize = 20;
+const MAX_MATCHES: usize = 50;
+
+/// Priority tags marking common words. Yomitan exports fold them into `P`.
+const COMMON_PRIORITIES: [&str; 6] = ["news1", "ichi1", "spec1", "spec2", "gai1", "P"];
+
+fn is_common(entry: &DictionaryEntry) -> bool {
+    entry
+        .kanji
+        .iter()
+        .flat_map(|k| &k.priorities)
+        .chain(entry.readings.iter().flat_map(|r| &r.priorities))
+        .any(|priority| COMMON_PRIORITIES.contains(&priority.as_str()))
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+}
+
+/// Opens a dictionary file, decompressing it on the fly when it ends in `.gz`.
+pub(crate) fn open_reader(path: &Path) -> Result<Box<dyn BufRead + Send>> {
+    let file = File::open(path)?;
+    Ok(match extension(path).as_deref() {
+        Some("gz") => Box::new(BufReader::new(GzDecoder::new(file))),
+        _ => Box::new(BufReader::new(file)),
+    })
+}
+
+/// JMdict XML (plain or gzipped) or a Yomitan `.zip`, picked by extension.
+fn open_entries(path: &Path) -> Result<Box<dyn Iterator<Item = Result<DictionaryEntry>> + Send>> {
+    if extension(path).as_deref() == Some("zip") {
+        return Ok(Box::new(yomitan::read_entries(path)?.into_iter().map(Ok)));
+    }
+    Ok(Box::new(JmdictReader::new(open_reader(path)?)))
+}
+
+fn send_batches(path: &Path, sender: mpsc::Sender<Vec<DictionaryEntry>>) -> Result<()> {
+    let mut batch = Vec::with_capacity(IMPORT_BATCH_SIZE);
+    for entry in open_entries(path)? {
+        batch.push(entry?);
+        if batch.len() == IMPORT_BATCH_SIZE
+            && sender.blocking_send(std::mem::take(&mut batch)).is_err()
+        {
+            // The receiver only goes away when the import failed on its side
+            return Ok(());
+        }
+    }
+    if !batch.is_empty() {
+        let _ = sender.blocking_send(batch);
+    }
+    Ok(())
+}
+
+/// Imports are written here first and only replace the dictionary once complete.
+const CREATE_STAGING_TABLES: &str = "
+    DROP TABLE IF EXISTS dictionary_staging_forms;
+    DROP TABLE IF EXISTS dictionary_staging_entries;
+    CREATE TABLE dictionary_staging_entries (
+        id INTEGER PRIMARY KEY,
+        is_common BOOLEAN NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE dictionary_staging_forms (
+        text TEXT NOT NULL,
+        entry_id INTEGER NOT NULL,
+        PRIMARY KEY (text, entry_id)
+    ) WITHOUT ROWID;";
+
+const DROP_STAGING_TABLES: &str = "
+    DROP TABLE IF EXISTS dictionary_staging_forms;
+    DROP TABLE IF EXISTS dictionary_staging_entries;";
+
+async fn insert_entry(conn: &mut SqliteConnection, entry: &DictionaryEntry) -> Result<()> {
+    sqlx::query("INSERT INTO dictionary_staging_entries (id, is_common, data) VALUES ($1, $2, $3)")
+        .bind(entry.id)
+        .bind(is_common(entry))
+        .bind(Json(entry))
+        .execute(&mut *conn)
+        .await?;
+
+    let forms = entry
+        .kanji
+        .iter()
+        .map(|k| &k.text)
+        .chain(entry.readings.iter().map(|r| &r.text));
+    for form in forms {
+        sqlx::query(
+            "INSERT OR IGNORE INTO dictionary_staging_forms (text, entry_id) VALUES ($1, $2)",
+        )
+        .bind(form)
+        .bind(entry.id)
+        .execute(&mut *conn)
+        .await?;
+    }
+    Ok(())
+}
+
+/// Replaces the dictionary with the contents of `path`. The import only
+/// counts as finished once `imported_at` is set.
+pub async fn import_file(state: &AppState, path: PathBuf) -> Result<DictionaryStatus> {
+    let _import = state.dictionary_import.lock().await;
+    replace_dictionary(&state.db, path).await
+}
+
+/// Writes the new dictionary aside in batches, so other writes are only
+/// held up briefly, then swaps it in with one short transaction. A failed
+/// import leaves the previous dictionary in place. Callers hold
+/// `AppState::dictionary_import`.
+async fn replace_dictionary(pool: &Pool<Sqlite>, path: PathBuf) -> Result<DictionaryStatus> {
+    sqlx::raw_sql(CREATE_STAGING_TABLES).execute(pool).await?;
+    let staged = stage_entries(pool, &path).await;
+    let swapped = match staged {
+        Ok(()) => swap_in_staged(pool, &path).await,
+        Err(err) => Err(err),
+    };
+    sqlx::raw_sql(DROP_STAGING_TABLES).execute(pool).await?;
+    swapped?;
+
+    dictionary_status(pool).await
+}
+
+async fn stage_entries(pool: &Pool<Sqlite>, path: &Path) -> Result<()> {
+    // Parsing is CPU bound, so it streams batches from a blocking thread
+    let (sender, mut receiver) = mpsc::channel(4);
+    let source = path.to_path_buf();
+    let parser = tauri::async_runtime::spawn_blocking(move || send_batches(&source, sender));
+
+    while let Some(batch) = receiver.recv().await {
+        let mut tx = pool.begin().await?;
+        for entry in &batch {
+            insert_entry(&mut tx, entry).await?;
+        }
+        tx.commit().await?;
+    }
+    parser.await??;
+    Ok(())
+}
+
+async fn swap_in_staged(pool: &Pool<Sqlite>, path: &Path) -> Result<()> {
+    let mut tx = pool.begin().await?;
+    sqlx::raw_sql(
+        "DELETE FROM dictionary_meta;
+         DELETE FROM dictionary_forms;
+         DELETE FROM dictionary_entries;
+         INSERT INTO dictionary_entries (id, is_common, data)
+             SELECT id, is_common, data FROM dictionary_staging_entries;
+         INSERT INTO dictionary_forms (text, entry_id)
+             SELECT text, entry_id FROM dictionary_staging_forms;",
+    )
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query(
+        "INSERT INTO dictionary_meta (key, value)
+         VALUES ('source', $1), ('imported_at', datetime('now'))",
+    )
+    .bind(path.to_string_lossy())
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+pub async fn dictionary_status(pool: &Pool<Sqlite>) -> Result<DictionaryStatus> {
+    let status = sqlx::query_as::<_, DictionaryStatus>(
+        "SELECT
+            (SELECT COUNT(*) FROM dictionary_entries) AS entry_count,
+            (SELECT value FROM dictionary_meta WHERE key = 'source') AS source,
+            (SELECT value FROM dictionary_meta WHERE key = 'imported_at') AS imported_at",
+    )
+    .fetch_one(pool)
+    .await?;
+    Ok(status)
+}
+
+/// Imports the dictionary shipped in `resources/jmdict`, unless an import
+/// already finished.
+pub async fn import_bundled(app: &AppHandle) -> Result<()> {
+    let state = app.state::<AppState>();
+    let _import = state.dictionary_import.lock().await;
+    if dictionary_status(&state.db).await?.imported_at.is_some() {
+        return Ok(());
+    }
+
+    let dir = app.path().resource_dir()?.join("resources").join("jmdict");
+    let Some(path) = BUNDLED_DICTIONARIES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+    else {
+        println!("No bundled dictionary found in {}", dir.display());
+        return Ok(());
+    };
+
+    println!("Importing dictionary from {}", path.display());
+    let status = replace_dictionary(&state.db, path).await?;
+    println!("Imported {} dictionary entries", status.entry_count);
+    Ok(())
+}
+
+/// Word class flags of every part of speech used by the entry.
+fn entry_flags(entry: &DictionaryEntry) -> u32 {
+    entry
+        .senses
+        .iter()
+        .flat_map(|sense| &sense.parts_of_speech)
+        .fold(0, |flags, code| flags | part_of_speech_flags(code))
+}
+
+/// Entries matching a prefix of `text`, either as written or once
+/// deinflected. Longer matches come first, then common words, then the ones
+/// needing fewer inflections.
+pub async fn lookup(pool: &Pool<Sqlite>, text: &str) -> Result<Vec<DictionaryMatch>> {
+    let text = text.trim();
+    let mut candidates: HashMap<String, Vec<(&str, Deinflection)>> = HashMap::new();
+    for (i, c) in text.char_indices().take(MAX_LOOKUP_LENGTH) {
+        let prefix = &text[..i + c.len_utf8()];
+        for deinflection in deinflect(prefix) {
+            candidates
+                .entry(deinflection.term.clone())
+                .or_default()
+                .push((prefix, deinflection));
+        }
+    }
+    if candidates.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let placeholders = (1..=candidates.len())
+        .map(|i| format!("${}", i))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let sql = format!(
+        "SELECT f.text, e.is_common, e.data FROM dictionary_forms f
+         JOIN dictionary_entries e ON e.id = f.entry_id
+         WHERE f.text IN ({})",
+        placeholders
+    );
+    let mut query = sqlx::query_as::<_, (String, bool, Json<DictionaryEntry>)>(&sql);
+    for term in candidates.keys() {
+        query = query.bind(term);
+    }
+
+    let mut matches = Vec::new();
+    for (form, is_common, Json(entry)) in query.fetch_all(pool).await? {
+        let flags = entry_flags(&entry);
+        // A deinflected form only counts when the entry is of the word class it implies
+        let best = candidates[&form]
+            .iter()
+            .filter(|(_, d)| d.conditions == ANY || d.conditions & flags != 0)
+            .max_by_key(|(source, d)| (source.len(), std::cmp::Reverse(d.reasons.len())));
+        if let Some((source, deinflection)) = best {
+            let order = (
+                source.chars().count(),
+                is_common,
+                deinflection.reasons.len(),
+            );
+            matches.push((
+                order,
+                DictionaryMatch {
+                    source: source.to_string(),
+                    matched: form,
+                    inflections: deinflection.reasons.iter().map(|r| r.to_string()).collect(),
+                    entry,
+                },
+            ));
+        }
+    }
+
+    matches.sort_by(|(a, x), (b, y)| {
+        b.0.cmp(&a.0)
+            .then(b.1.cmp(&a.1))
+            .then(a.2.cmp(&b.2))
+            .then(x.entry.id.cmp(&y.entry.id))
+    });
+
+    // An entry matched through several forms is only listed for the best one
+    let mut seen = HashSet::new();
+    Ok(matches
+        .into_iter()
+        .map(|(_, m)| m)
+        .filter(|m| seen.insert(m.entry.id))
+        .take(MAX_MATCHES)
+        .collect())
+}
+
+#[tauri::command]
+pub async fn lookup_word(text: String, state: State<'_, AppState>) -> Result<Vec<DictionaryMatch>> {
+    lookup(&state.db, &text).await
+}
+
+#[tauri::command]
+pub async fn import_dictionary(
+    path: String,
+    state: State<'_, AppState>,
+) -> Result<DictionaryStatus> {
+    import_file(&state, PathBuf::from(path)).await
+}
+
+#[tauri::command]
+pub async fn get_dictionary_status(state: State<'_, AppState>) -> Result<DictionaryStatus> {
+    dictionary_status(&state.db).await
+}
@@ -0,0 +1,156 @@
+//! Reader for Yomitan dictionary archives, e.g. the JMdict exports published
+//! by jmdict-yomitan. Every term bank row holds one sense of one form pair,
+//! so rows are merged back into entries by their sequence number.
+
+use crate::models::{DictionaryEntry, KanjiForm, ReadingForm, Sense};
+use crate::Result;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+use zip::ZipArchive;
+
+/// `[expression, reading, definitionTags, rules, score, glossary, sequence, termTags]`
+type TermRow = (String, String, String, String, f64, Vec<Value>, i64, String);
+
+pub fn read_entries(path: &Path) -> Result<Vec<DictionaryEntry>> {
+    let mut archive = ZipArchive::new(BufReader::new(File::open(path)?))?;
+
+    let mut banks = archive
+        .file_names()
+        .filter_map(|name| Some((bank_number(name)?, name.to_string())))
+        .collect::<Vec<_>>();
+    if banks.is_empty() {
+        return Err("No term banks found in the Yomitan dictionary".into());
+    }
+    banks.sort();
+
+    let mut entries = BTreeMap::new();
+    for (_, name) in banks {
+        let rows: Vec<TermRow> = serde_json::from_reader(BufReader::new(archive.by_name(&name)?))?;
+        for row in rows {
+            add_row(&mut entries, row);
+        }
+    }
+    Ok(entries.into_values().collect())
+}
+
+/// `term_bank_12.json` -> 12
+fn bank_number(name: &str) -> Option<u32> {
+    name.strip_prefix("term_bank_")?
+        .strip_suffix(".json")?
+        .parse()
+        .ok()
+}
+
+fn add_row(entries: &mut BTreeMap<i64, DictionaryEntry>, row: TermRow) {
+    let (expression, reading, definition_tags, _, _, glossary, sequence, term_tags) = row;
+    let entry = entries.entry(sequence).or_insert_with(|| DictionaryEntry {
+        id: sequence,
+        ..Default::default()
+    });
+
+    // Kana-only terms leave the reading empty
+    let reading = if reading.is_empty() {
+        expression.clone()
+    } else {
+        reading
+    };
+    let priorities = split_tags(&term_tags);
+
+    if expression != reading && !entry.kanji.iter().any(|k| k.text == expression) {
+        entry.kanji.push(KanjiForm {
+            text: expression,
+            priorities: priorities.clone(),
+            ..Default::default()
+        });
+    }
+    if !entry.readings.iter().any(|r| r.text == reading) {
+        entry.readings.push(ReadingForm {
+            text: reading,
+            priorities,
+            ..Default::default()
+        });
+    }
+
+    let sense = Sense {
+        parts_of_speech: split_tags(&definition_tags),
+        glosses: glossary.iter().flat_map(gloss_texts).collect(),
+        ..Default::default()
+    };
+    // The same senses are repeated for every form pair of the entry
+    if !sense.glosses.is_empty() && !entry.senses.iter().any(|s| s.glosses == sense.glosses) {
+        entry.senses.push(sense);
+    }
+}
+
+fn split_tags(tags: &str) -> Vec<String> {
+    tags.split_whitespace().map(String::from).collect()
+}
+
+/// Glossary items are plain strings in older exports and structured content
+/// in newer ones, where the actual glosses are the `li` items of the list
+/// marked `"data": {"content": "glossary"}`.
+fn gloss_texts(gloss: &Value) -> Vec<String> {
+    match gloss {
+        Value::String(text) => vec![text.clone()],
+        Value::Object(object) => match object.get("type").and_then(Value::as_str) {
+            Some("text") => object
+                .get("text")
+                .and_then(Value::as_str)
+                .map(String::from)
+                .into_iter()
+                .collect(),
+            Some("structured-content") => {
+                let content = object.get("content").unwrap_or(&Value::Null);
+                let mut glosses = Vec::new();
+                collect_glossary_items(content, false, &mut glosses);
+                if glosses.is_empty() {
+                    glosses.push(plain_text(content));
+                }
+                glosses.retain(|gloss| !gloss.is_empty());
+                glosses
+            }
+            // Images
+            _ => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+fn collect_glossary_items(content: &Value, in_glossary: bool, glosses: &mut Vec<String>) {
+    match content {
+        Value::Array(items) => {
+            for item in items {
+                collect_glossary_items(item, in_glossary, glosses);
+            }
+        }
+        Value::Object(node) => {
+            let tag = node.get("tag").and_then(Value::as_str);
+            let children = node.get("content").unwrap_or(&Value::Null);
+            if in_glossary && tag == Some("li") {
+                glosses.push(plain_text(children));
+                return;
+            }
+            let is_glossary = node
+                .get("data")
+                .and_then(|data| data.get("content"))
+                .and_then(Value::as_str)
+                == Some("glossary");
+            collect_glossary_items(children, in_glossary || is_glossary, glosses);
+        }
+        _ => {}
+    }
+}
+
+fn plain_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items.iter().map(plain_text).collect(),
+        Value::Object(node) if node.get("tag").and_then(Value::as_str) != Some("img") => {
+            plain_text(node.get("content").unwrap_or(&Value::Null))
+        }
+        _ => String::new(),
+    }
+}
@@ -5,6 +5,9 @@ pub enum Error {
     #[error("Database error: {0}")]
     Database(#[from] sqlx::Error),
 
+    #[error("Migration error: {0}")]
+    Migration(#[from] sqlx::migrate::MigrateError),
+
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
@@ -17,9 +20,39 @@ pub enum Error {
     #[error("Tokenizer error: {0}")]
     Tokenizer(String),
 
+    #[error("Segmenter error: {0}")]
+    Segmenter(#[from] lindera::error::LinderaError),
+
+    #[error("XML error: {0}")]
+    Xml(#[from] quick_xml::Error),
+
+    #[error("Zip error: {0}")]
+    Zip(#[from] zip::result::ZipError),
+
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("AnkiConnect error: {0}")]
+    AnkiConnect(String),
+
     #[error("Image processing error: {0}")]
     Image(#[from] image::ImageError),
 
+    #[error("Screen capture error: {0}")]
+    Capture(#[from] xcap::XCapError),
+
+    #[error("Invalid config: {0}")]
+    Config(String),
+
+    #[error("Invalid shortcut '{shortcut}': {reason}")]
+    InvalidShortcut { shortcut: String, reason: String },
+
+    #[error("Could not register {shortcut}, it may already be used by another application: {reason}")]
+    ShortcutConflict { shortcut: String, reason: String },
+
     #[error("Global shortcut error: {0}")]
     GlobalShortcut(#[from] tauri_plugin_global_shortcut::Error),
 
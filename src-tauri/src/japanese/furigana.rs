@@ -0,0 +1,225 @@
+//! Splits words into kanji runs with their readings, for ruby text.
+
+use crate::japanese::kana::{is_kanji, to_hiragana};
+use crate::models::{FuriganaSegment, Token};
+
+struct Run {
+    text: String,
+    is_kanji: bool,
+}
+
+fn split_runs(surface: &str) -> Vec<Run> {
+    let mut runs: Vec<Run> = Vec::new();
+    for c in surface.chars() {
+        match runs.last_mut() {
+            Some(run) if run.is_kanji == is_kanji(c) => run.text.push(c),
+            _ => runs.push(Run {
+                text: c.to_string(),
+                is_kanji: is_kanji(c),
+            }),
+        }
+    }
+    runs
+}
+
+/// Gives every kanji run a non-empty part of `reading`, with every kana run
+/// matching its own part exactly. Shorter kanji readings are tried first.
+fn match_runs(runs: &[Run], reading: &[char]) -> Option<Vec<String>> {
+    let Some((run, rest)) = runs.split_first() else {
+        return reading.is_empty().then(Vec::new);
+    };
+
+    if run.is_kanji {
+        // Keep enough of the reading for the runs that follow
+        let reserved: usize = rest
+            .iter()
+            .map(|run| {
+                if run.is_kanji {
+                    1
+                } else {
+                    run.text.chars().count()
+                }
+            })
+            .sum();
+        for len in 1..=reading.len().saturating_sub(reserved) {
+            if let Some(mut readings) = match_runs(rest, &reading[len..]) {
+                readings.insert(0, reading[..len].iter().collect());
+                return Some(readings);
+            }
+        }
+        None
+    } else {
+        let kana = to_hiragana(&run.text).chars().collect::<Vec<_>>();
+        let remaining = reading.strip_prefix(kana.as_slice())?;
+        let mut readings = match_runs(rest, remaining)?;
+        readings.insert(0, String::new());
+        Some(readings)
+    }
+}
+
+/// Aligns a word with its reading (katakana or hiragana). Okurigana stay
+/// outside the ruby (`食べる` -> `食|た` `べる`); words whose reading does not
+/// line up with the kana they contain, like most jukujikun, get the reading
+/// over the whole word.
+pub fn align(surface: &str, reading: &str) -> Vec<FuriganaSegment> {
+    let runs = split_runs(surface);
+    if !runs.iter().any(|run| run.is_kanji) {
+        return vec![FuriganaSegment::plain(surface)];
+    }
+
+    let reading = to_hiragana(reading).chars().collect::<Vec<_>>();
+    match match_runs(&runs, &reading) {
+        Some(readings) => runs
+            .into_iter()
+            .zip(readings)
+            .map(|(run, reading)| FuriganaSegment {
+                reading: run.is_kanji.then_some(reading),
+                text: run.text,
+            })
+            .collect(),
+        None => vec![FuriganaSegment {
+            text: surface.to_string(),
+            reading: Some(reading.into_iter().collect()),
+        }],
+    }
+}
+
+/// Furigana for a whole line from its tokens. Text the segmenter skipped and
+/// words without a known reading are kept as plain segments.
+pub fn line_furigana(text: &str, tokens: &[Token]) -> Vec<FuriganaSegment> {
+    let chars = text.chars().collect::<Vec<_>>();
+    let mut segments = Vec::new();
+    let mut position = 0;
+
+    for token in tokens {
+        if token.start > position {
+            segments.push(FuriganaSegment::plain(
+                chars[position..token.start].iter().collect::<String>(),
+            ));
+        }
+        match &token.reading {
+            Some(reading) => segments.extend(align(&token.surface, reading)),
+            None => segments.push(FuriganaSegment::plain(&token.surface)),
+        }
+        position = position.max(token.end);
+    }
+    if position < chars.len() {
+        segments.push(FuriganaSegment::plain(
+            chars[position..].iter().collect::<String>(),
+        ));
+    }
+
+    // Merge neighbouring plain segments so the overlay renders fewer elements
+    let mut merged: Vec<FuriganaSegment> = Vec::with_capacity(segments.len());
+    for segment in segments {
+        match merged.last_mut() {
+            Some(last) if last.reading.is_none() && segment.reading.is_none() => {
+                last.text.push_str(&segment.text)
+            }
+            _ => merged.push(segment),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ruby(text: &str, reading: &str) -> FuriganaSegment {
+        FuriganaSegment {
+            text: text.to_string(),
+            reading: Some(reading.to_string()),
+        }
+    }
+
+    fn token(surface: &str, reading: Option<&str>, start: usize) -> Token {
+        Token {
+            surface: surface.to_string(),
+            base_form: surface.to_string(),
+            reading: reading.map(str::to_string),
+            part_of_speech: Vec::new(),
+            conjugation_type: None,
+            conjugation_form: None,
+            start,
+            end: start + surface.chars().count(),
+        }
+    }
+
+    #[test]
+    fn keeps_okurigana_outside_the_ruby() {
+        assert_eq!(
+            align("食べる", "タベル"),
+            vec![ruby("食", "た"), FuriganaSegment::plain("べる")]
+        );
+    }
+
+    #[test]
+    fn splits_kanji_runs_around_kana() {
+        assert_eq!(
+            align("取り扱い", "トリアツカイ"),
+            vec![
+                ruby("取", "と"),
+                FuriganaSegment::plain("り"),
+                ruby("扱", "あつか"),
+                FuriganaSegment::plain("い"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reads_a_kanji_run_as_a_whole() {
+        assert_eq!(align("今日", "キョウ"), vec![ruby("今日", "きょう")]);
+    }
+
+    #[test]
+    fn leaves_kana_words_plain() {
+        assert_eq!(
+            align("ありがとう", "アリガトウ"),
+            vec![FuriganaSegment::plain("ありがとう")]
+        );
+    }
+
+    #[test]
+    fn puts_a_mismatched_reading_over_the_whole_word() {
+        assert_eq!(align("食べる", "ノム"), vec![ruby("食べる", "のむ")]);
+        assert_eq!(
+            match_runs(&split_runs("食べる"), &"のむ".chars().collect::<Vec<_>>()),
+            None
+        );
+    }
+
+    #[test]
+    fn matches_runs_to_parts_of_the_reading() {
+        let reading = "とりあつかい".chars().collect::<Vec<_>>();
+        assert_eq!(
+            match_runs(&split_runs("取り扱い"), &reading),
+            Some(vec![
+                "と".to_string(),
+                String::new(),
+                "あつか".to_string(),
+                String::new(),
+            ])
+        );
+    }
+
+    #[test]
+    fn keeps_skipped_text_and_unknown_words_plain() {
+        let text = "「今日、食べる」";
+        let tokens = [
+            token("今日", Some("キョウ"), 1),
+            token("、", None, 3),
+            token("食べる", Some("タベル"), 4),
+        ];
+        assert_eq!(
+            line_furigana(text, &tokens),
+            vec![
+                FuriganaSegment::plain("「"),
+                ruby("今日", "きょう"),
+                FuriganaSegment::plain("、"),
+                ruby("食", "た"),
+                FuriganaSegment::plain("べる」"),
+            ]
+        );
+    }
+}
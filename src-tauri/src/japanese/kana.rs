@@ -0,0 +1,26 @@
+//! Character classes and kana conversion.
+
+/// Katakana with a hiragana counterpart, `ァ` to `ヴ`.
+const KATAKANA: std::ops::RangeInclusive<char> = 'ァ'..='ヴ';
+const KATAKANA_TO_HIRAGANA: u32 = 0x60;
+
+/// Kanji, plus the repetition mark `々` and `ヶ`/`〆` which are read like them.
+pub fn is_kanji(c: char) -> bool {
+    matches!(c,
+        '\u{4E00}'..='\u{9FFF}'   // CJK Unified Ideographs
+        | '\u{3400}'..='\u{4DBF}' // Extension A
+        | '\u{F900}'..='\u{FAFF}' // Compatibility Ideographs
+        | '々' | '〆' | 'ヶ')
+}
+
+pub fn to_hiragana(text: &str) -> String {
+    text.chars()
+        .map(|c| {
+            if KATAKANA.contains(&c) {
+                char::from_u32(c as u32 - KATAKANA_TO_HIRAGANA).unwrap_or(c)
+            } else {
+                c
+            }
+        })
+        .collect()
+}
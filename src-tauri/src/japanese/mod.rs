@@ -0,0 +1,5 @@
+pub mod furigana;
+pub mod kana;
+pub mod segmenter;
+
+pub use segmenter::Segmenter;
@@ -0,0 +1,59 @@
+//! Morphological segmentation of recognized lines with Lindera.
+
+use crate::models::Token;
+use crate::Result;
+use lindera::dictionary::load_fs_dictionary;
+use lindera::mode::Mode;
+use std::borrow::Cow;
+use std::path::Path;
+
+/// Splits text into morphemes using a compiled IPADIC dictionary.
+pub struct Segmenter {
+    inner: lindera::segmenter::Segmenter,
+}
+
+impl Segmenter {
+    /// Loads a dictionary built with `lindera build` from the IPADIC sources.
+    pub fn from_dictionary_dir(dir: &Path) -> Result<Self> {
+        let dictionary = load_fs_dictionary(dir)?;
+        Ok(Self {
+            inner: lindera::segmenter::Segmenter::new(Mode::Normal, dictionary, None),
+        })
+    }
+
+    pub fn tokenize(&self, text: &str) -> Result<Vec<Token>> {
+        let mut tokens = Vec::new();
+        for mut token in self.inner.segment(Cow::Borrowed(text))? {
+            // IPADIC fills unused fields with `*`
+            let mut field = |name: &str| {
+                token
+                    .get(name)
+                    .filter(|value| !value.is_empty() && *value != "*")
+                    .map(String::from)
+            };
+
+            let part_of_speech = ["major_pos", "pos_detail_1", "pos_detail_2", "pos_detail_3"]
+                .into_iter()
+                .filter_map(&mut field)
+                .collect();
+            let base_form = field("base_form");
+            let reading = field("reading");
+            let conjugation_type = field("conjugation_type");
+            let conjugation_form = field("conjugation_form");
+
+            let surface = token.surface.to_string();
+            let start = text[..token.byte_start].chars().count();
+            tokens.push(Token {
+                base_form: base_form.unwrap_or_else(|| surface.clone()),
+                reading,
+                part_of_speech,
+                conjugation_type,
+                conjugation_form,
+                start,
+                end: start + surface.chars().count(),
+                surface,
+            });
+        }
+        Ok(tokens)
+    }
+}
@@ -0,0 +1,76 @@
+//! Reader for the KANJIDIC2 XML release (`kanjidic2.xml`, optionally gzipped).
+
+use super::attribute;
+use crate::dictionary::jmdict::push_reference;
+use crate::models::KanjiInfo;
+use crate::Result;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::BufRead;
+
+/// Reads every `<character>` of the file.
+pub fn read_characters<R: BufRead>(source: R) -> Result<Vec<KanjiInfo>> {
+    let mut reader = Reader::from_reader(source);
+    let mut buf = Vec::new();
+    let mut characters = Vec::new();
+
+    let mut current: Option<KanjiInfo> = None;
+    let mut text = String::new();
+    // Attributes of the element whose text is being read
+    let mut reading_type = None;
+    let mut is_english = true;
+    let mut is_classical = false;
+
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(start) => {
+                text.clear();
+                match start.local_name().as_ref() {
+                    b"character" => current = Some(KanjiInfo::default()),
+                    b"reading" => reading_type = attribute(&start, "r_type"),
+                    b"meaning" => {
+                        is_english =
+                            matches!(attribute(&start, "m_lang").as_deref(), None | Some("en"))
+                    }
+                    b"rad_value" => {
+                        is_classical = attribute(&start, "rad_type").as_deref() == Some("classical")
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(content) => {
+                text.push_str(&content.decode().map_err(quick_xml::Error::from)?)
+            }
+            Event::GeneralRef(reference) => push_reference(&mut text, &reference)?,
+            Event::End(end) => {
+                let Some(kanji) = current.as_mut() else {
+                    continue;
+                };
+                let value = std::mem::take(&mut text).trim().to_string();
+                match end.local_name().as_ref() {
+                    b"literal" => kanji.literal = value,
+                    b"reading" => match reading_type.as_deref() {
+                        Some("ja_on") => kanji.on_readings.push(value),
+                        Some("ja_kun") => kanji.kun_readings.push(value),
+                        _ => {}
+                    },
+                    b"nanori" => kanji.nanori.push(value),
+                    b"meaning" if is_english => kanji.meanings.push(value),
+                    // Later counts are common miscounts
+                    b"stroke_count" if kanji.stroke_count.is_none() => {
+                        kanji.stroke_count = value.parse().ok()
+                    }
+                    b"grade" => kanji.grade = value.parse().ok(),
+                    b"jlpt" => kanji.jlpt = value.parse().ok(),
+                    b"freq" => kanji.frequency = value.parse().ok(),
+                    b"rad_value" if is_classical => kanji.radical = value.parse().ok(),
+                    b"character" => characters.extend(current.take()),
+                    _ => {}
+                }
+            }
+            Event::Eof => return Ok(characters),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,56 @@
+//! Reader for the single-file KanjiVG release (`kanjivg-<date>.xml`).
+
+use super::attribute;
+use crate::Result;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io::BufRead;
+
+/// A kanji with the SVG path data of its strokes, in stroke order.
+pub struct KanjiStrokes {
+    pub literal: String,
+    pub paths: Vec<String>,
+}
+
+/// `kvg:kanji_04e9c` -> `亜`. Variants such as `kvg:kanji_04e9c-Kaisho` are skipped.
+fn literal_from_id(id: &str) -> Option<String> {
+    let code = id.strip_prefix("kvg:kanji_")?;
+    let literal = char::from_u32(u32::from_str_radix(code, 16).ok()?)?;
+    Some(literal.to_string())
+}
+
+pub fn read_strokes<R: BufRead>(source: R) -> Result<Vec<KanjiStrokes>> {
+    let mut reader = Reader::from_reader(source);
+    let mut buf = Vec::new();
+    let mut kanji = Vec::new();
+    let mut current: Option<KanjiStrokes> = None;
+
+    loop {
+        buf.clear();
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(start) | Event::Empty(start) => match start.local_name().as_ref() {
+                b"kanji" => {
+                    current = attribute(&start, "id")
+                        .as_deref()
+                        .and_then(literal_from_id)
+                        .map(|literal| KanjiStrokes {
+                            literal,
+                            paths: Vec::new(),
+                        });
+                }
+                b"path" => {
+                    if let (Some(strokes), Some(path)) = (current.as_mut(), attribute(&start, "d"))
+                    {
+                        strokes.paths.push(path);
+                    }
+                }
+                _ => {}
+            },
+            Event::End(end) if end.local_name().as_ref() == b"kanji" => {
+                kanji.extend(current.take());
+            }
+            Event::Eof => return Ok(kanji),
+            _ => {}
+        }
+    }
+}
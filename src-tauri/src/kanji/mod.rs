@@ -0,0 +1,140 @@
+pub mod kanjidic;
+pub mod kanjivg;
+
+use crate::dictionary::open_reader;
+use crate::japanese::kana::is_kanji;
+use crate::models::KanjiInfo;
+use crate::state::AppState;
+use crate::Result;
+use quick_xml::events::BytesStart;
+use sqlx::{types::Json, Pool, Sqlite};
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+/// Files looked for in `resources/kanji` on first start.
+const BUNDLED_KANJIDIC: [&str; 2] = ["kanjidic2.xml.gz", "kanjidic2.xml"];
+const BUNDLED_KANJIVG: [&str; 2] = ["kanjivg.xml.gz", "kanjivg.xml"];
+
+fn attribute(start: &BytesStart, name: &str) -> Option<String> {
+    let attribute = start.try_get_attribute(name).ok()??;
+    Some(String::from_utf8_lossy(&attribute.value).into_owned())
+}
+
+/// Replaces the kanji table with the contents of a KANJIDIC2 file and
+/// returns the number of characters imported.
+pub async fn import_kanjidic_file(pool: &Pool<Sqlite>, path: PathBuf) -> Result<usize> {
+    // Around 13k characters, small enough to parse in one go
+    let characters = tauri::async_runtime::spawn_blocking(move || {
+        kanjidic::read_characters(open_reader(&path)?)
+    })
+    .await??;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM kanji").execute(&mut *tx).await?;
+    for kanji in &characters {
+        sqlx::query("INSERT OR REPLACE INTO kanji (literal, data) VALUES ($1, $2)")
+            .bind(&kanji.literal)
+            .bind(Json(kanji))
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    Ok(characters.len())
+}
+
+/// Replaces the stroke data with the contents of a KanjiVG file and returns
+/// the number of kanji imported.
+pub async fn import_kanjivg_file(pool: &Pool<Sqlite>, path: PathBuf) -> Result<usize> {
+    let strokes =
+        tauri::async_runtime::spawn_blocking(move || kanjivg::read_strokes(open_reader(&path)?))
+            .await??;
+
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM kanji_strokes")
+        .execute(&mut *tx)
+        .await?;
+    for kanji in &strokes {
+        sqlx::query("INSERT OR REPLACE INTO kanji_strokes (literal, paths) VALUES ($1, $2)")
+            .bind(&kanji.literal)
+            .bind(Json(&kanji.paths))
+            .execute(&mut *tx)
+            .await?;
+    }
+    tx.commit().await?;
+
+    Ok(strokes.len())
+}
+
+fn find_bundled(dir: &Path, names: &[&str]) -> Option<PathBuf> {
+    names
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.exists())
+}
+
+/// Imports the files shipped in `resources/kanji` into empty tables.
+pub async fn import_bundled(app: &AppHandle) -> Result<()> {
+    let pool = app.state::<AppState>().db.clone();
+    let dir = app.path().resource_dir()?.join("resources").join("kanji");
+
+    let (kanji_count, stroke_count) = sqlx::query_as::<_, (i64, i64)>(
+        "SELECT (SELECT COUNT(*) FROM kanji), (SELECT COUNT(*) FROM kanji_strokes)",
+    )
+    .fetch_one(&pool)
+    .await?;
+
+    if kanji_count == 0 {
+        if let Some(path) = find_bundled(&dir, &BUNDLED_KANJIDIC) {
+            let count = import_kanjidic_file(&pool, path).await?;
+            println!("Imported {} kanji", count);
+        }
+    }
+    if stroke_count == 0 {
+        if let Some(path) = find_bundled(&dir, &BUNDLED_KANJIVG) {
+            let count = import_kanjivg_file(&pool, path).await?;
+            println!("Imported stroke data for {} kanji", count);
+        }
+    }
+    Ok(())
+}
+
+/// Information on every distinct kanji of `text`, in order of appearance.
+/// Characters missing from KANJIDIC2 are left out.
+pub async fn kanji_info(pool: &Pool<Sqlite>, text: &str) -> Result<Vec<KanjiInfo>> {
+    let mut seen = HashSet::new();
+    let mut infos = Vec::new();
+
+    for literal in text.chars().filter(|&c| is_kanji(c) && seen.insert(c)) {
+        let row = sqlx::query_as::<_, (Json<KanjiInfo>, Option<Json<Vec<String>>>)>(
+            "SELECT k.data, s.paths FROM kanji k
+             LEFT JOIN kanji_strokes s ON s.literal = k.literal
+             WHERE k.literal = $1",
+        )
+        .bind(literal.to_string())
+        .fetch_optional(pool)
+        .await?;
+
+        if let Some((Json(mut info), strokes)) = row {
+            info.strokes = strokes.map(|Json(paths)| paths).unwrap_or_default();
+            infos.push(info);
+        }
+    }
+    Ok(infos)
+}
+
+#[tauri::command]
+pub async fn get_kanji_info(text: String, state: State<'_, AppState>) -> Result<Vec<KanjiInfo>> {
+    kanji_info(&state.db, &text).await
+}
+
+#[tauri::command]
+pub async fn import_kanjidic(path: String, state: State<'_, AppState>) -> Result<usize> {
+    import_kanjidic_file(&state.db, PathBuf::from(path)).await
+}
+
+#[tauri::command]
+pub async fn import_kanjivg(path: String, state: State<'_, AppState>) -> Result<usize> {
+    import_kanjivg_file(&state.db, PathBuf::from(path)).await
+}
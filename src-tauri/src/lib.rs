@@ -1,12 +1,33 @@
+mod anki;
+mod capture;
+mod config;
 mod db;
+mod dictionary;
 mod error;
-mod models;
-mod ocr;
+pub mod japanese;
+mod kanji;
+pub mod models;
+pub mod ocr;
+mod shortcuts;
+mod srs;
 mod state;
 
-use db::{add_todo, delete_todo, get_todos, toggle_todo};
-use image::DynamicImage;
-use ocr::run_ocr;
+use anki::{export_anki, send_to_anki};
+use capture::{capture_region, get_capture_mode, list_monitors, set_capture_mode};
+use config::{get_config, set_config};
+use db::captures::{delete_capture, get_capture, list_captures};
+use db::groups::{
+    add_group, add_word_to_group, delete_group, get_groups, remove_word_from_group, update_group,
+};
+use db::reviews::{get_due_cards, submit_review};
+use db::stats::get_user_stats;
+use db::words::{
+    add_word, delete_word, get_tags, get_word, get_words, search_words, set_word_tags,
+    toggle_favorite, update_word,
+};
+use dictionary::{get_dictionary_status, import_dictionary, lookup_word};
+use kanji::{get_kanji_info, import_kanjidic, import_kanjivg};
+use ocr::{OcrEngine, OcrModelPaths};
 use state::AppState;
 use std::sync::Mutex;
 use tauri::{
@@ -15,12 +36,8 @@ use tauri::{
     tray::TrayIconBuilder,
     AppHandle, Emitter, Manager,
 };
-use tauri_plugin_global_shortcut::{Code, Modifiers, ShortcutState};
-use xcap::Monitor;
 
 pub use error::{Error, Result};
-use ort::session::Session;
-use tokenizers::Tokenizer;
 
 #[tauri::command]
 async fn hide_app_window(handle: AppHandle) -> Result<()> {
@@ -30,37 +47,6 @@ async fn hide_app_window(handle: AppHandle) -> Result<()> {
     Ok(())
 }
 
-async fn take_screenshot(app: &AppHandle) {
-    let monitors = Monitor::all().unwrap();
-
-    if let Some(monitor) = monitors.first() {
-        let image = monitor.capture_image().unwrap();
-
-        if let Some(window) = app.get_webview_window("overlay") {
-            if window.is_visible().unwrap_or(false) {
-                window.hide().unwrap();
-                app.emit("reset-ocr", ()).unwrap();
-            } else {
-                window.show().unwrap();
-                window.set_focus().unwrap();
-                window.set_always_on_top(true).unwrap();
-                let dynamic_image = DynamicImage::ImageRgba8(image.clone());
-                let ocr_box = run_ocr(app, dynamic_image).await;
-                match ocr_box {
-                    Ok(bbox) => {
-                        app.emit("run-ocr", bbox).unwrap();
-                    }
-                    Err(err) => {
-                        app.emit("error", err.to_string()).unwrap();
-                    }
-                }
-            }
-        } else {
-            println!("Overlay window not found!");
-        }
-    }
-}
-
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_process::init())
@@ -73,33 +59,10 @@ pub fn run() {
             let app_handle = app.handle().clone();
             ocr::init_ort(&app_handle).expect("Failed to initialize ORT");
 
-            let handle = app.handle().clone();
-            let ctrl_shift_s = tauri_plugin_global_shortcut::Shortcut::new(
-                Some(Modifiers::CONTROL | Modifiers::SHIFT),
-                Code::KeyS,
-            );
+            // Shortcuts are registered from the config once the state is managed
             app.handle().plugin(
                 tauri_plugin_global_shortcut::Builder::new()
-                    .with_shortcuts([ctrl_shift_s.clone()])?
-                    .with_handler(move |handler_app, shortcut, event| {
-                        if shortcut == &ctrl_shift_s && event.state == ShortcutState::Pressed {
-                            let processing = handler_app.state::<AppState>().is_processing.clone();
-                            if processing.swap(true, std::sync::atomic::Ordering::SeqCst) {
-                                println!("OCR process is already running, cancelling new request");
-                                return; // Already running, ignore this press
-                            }
-
-                            // 1. Clone the handle to move into the async block
-                            let shortcut_handle = handle.clone();
-                            // 2. Spawn the async task
-                            tauri::async_runtime::spawn(async move {
-                                // Now you can await your async function
-                                let _ = take_screenshot(&shortcut_handle).await;
-                                // Reset flag when done
-                                processing.store(false, std::sync::atomic::Ordering::SeqCst);
-                            });
-                        }
-                    })
+                    .with_handler(shortcuts::handle)
                     .build(),
             )?;
 
@@ -108,33 +71,40 @@ pub fn run() {
                 let db_pool = db::init_db(&app_handle).await.expect("Failed to init DB");
 
                 let resource_path = app_handle.path().resource_dir().unwrap();
-                let manga_ocr_path = resource_path.join("resources").join("manga_ocr");
-                let enc_model_path = manga_ocr_path.join("encoder_model.onnx");
-                let dec_model_path = manga_ocr_path.join("decoder_model.onnx");
-                let tokenizer_path = manga_ocr_path.join("tokenizer.json");
-
-                let pp_ocr_path = resource_path.join("resources").join("paddle_ocr");
-                let det_model_path = pp_ocr_path.join("ppocrv5-mobile-det.onnx");
-
-                let enc_session = Session::builder()?.commit_from_file(enc_model_path)?;
-                let dec_session = Session::builder()?.commit_from_file(dec_model_path)?;
-                let det_session = Session::builder()?.commit_from_file(det_model_path)?;
-
-                let tokenizer = Tokenizer::from_file(tokenizer_path)
-                    .map_err(|e| Error::Tokenizer(e.to_string()))?;
+                let engine = OcrEngine::new(&OcrModelPaths::from_resource_dir(&resource_path))?
+                    .with_observer(ocr::emit_debug_images(app_handle.clone()));
 
                 app_handle.manage(AppState {
                     db: db_pool,
                     is_processing: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
-                    det_session: Mutex::new(det_session),
-                    enc_session: Mutex::new(enc_session),
-                    dec_session: Mutex::new(dec_session),
-                    tokenizer: Mutex::new(tokenizer),
+                    shortcuts_registered: std::sync::atomic::AtomicBool::new(false),
+                    engine: Mutex::new(engine),
+                    config: Mutex::new(config::load(&app_handle)),
+                    pending_capture: Mutex::new(None),
+                    dictionary_import: tokio::sync::Mutex::new(()),
                 });
                 Ok::<(), anyhow::Error>(())
             })
             .expect("Failed to initialize state");
 
+            // A taken shortcut should not keep the app from starting, it can be changed in the settings
+            let config = app.state::<AppState>().config.lock().unwrap().clone();
+            if let Err(err) = shortcuts::register(app.handle(), &config) {
+                println!("Failed to register the shortcuts: {}", err);
+                let _ = app.emit("error", err.to_string());
+            }
+
+            // The first import takes a while, lookups just come back empty until it is done
+            let dictionary_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(err) = dictionary::import_bundled(&dictionary_handle).await {
+                    println!("Failed to import the bundled dictionary: {}", err);
+                }
+                if let Err(err) = kanji::import_bundled(&dictionary_handle).await {
+                    println!("Failed to import the bundled kanji data: {}", err);
+                }
+            });
+
             let icon_bytes = include_bytes!("../icons/lang.ico");
             let quit_i = MenuItem::with_id(app, "quit", "Quit", true, None::<&str>)?;
             let show_i = MenuItem::with_id(app, "show", "Show dashboard", true, None::<&str>)?;
@@ -161,10 +131,41 @@ pub fn run() {
         })
         .invoke_handler(tauri::generate_handler![
             hide_app_window,
-            get_todos,
-            add_todo,
-            toggle_todo,
-            delete_todo,
+            capture_region,
+            list_monitors,
+            get_capture_mode,
+            set_capture_mode,
+            get_config,
+            set_config,
+            get_words,
+            search_words,
+            get_word,
+            add_word,
+            update_word,
+            delete_word,
+            toggle_favorite,
+            get_tags,
+            set_word_tags,
+            get_groups,
+            add_group,
+            update_group,
+            delete_group,
+            add_word_to_group,
+            remove_word_from_group,
+            get_due_cards,
+            submit_review,
+            get_user_stats,
+            export_anki,
+            send_to_anki,
+            list_captures,
+            get_capture,
+            delete_capture,
+            lookup_word,
+            import_dictionary,
+            get_dictionary_status,
+            get_kanji_info,
+            import_kanjidic,
+            import_kanjivg,
         ])
         .on_window_event(|window, event| {
             if let tauri::WindowEvent::CloseRequested { api, .. } = event {
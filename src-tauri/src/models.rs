@@ -1,6 +1,9 @@
 use serde::{Deserialize, Serialize};
-use sqlx::FromRow;
+use sqlx::{types::Json, FromRow};
 
+/// A recognized text line. `x`, `y`, `width` and `height` are physical pixels
+/// of the captured image; `logical` is the same box in the monitor's logical
+/// pixels, which is what the overlay webview positions elements in.
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct OcrBox {
     pub text: String,
@@ -8,12 +11,655 @@ pub struct OcrBox {
     pub y: u32,
     pub width: u32,
     pub height: u32,
+    pub logical: Rect,
+    /// Corners of the detected text region clockwise from the top left of the
+    /// text, whose top edge is turned at most 45° from horizontal. In the same
+    /// pixels as `x` and `y`, which hold its axis-aligned bounds.
+    #[serde(default)]
+    pub quad: [Point; 4],
+    /// Morphemes of `text`, empty when no segmentation dictionary is installed.
+    #[serde(default)]
+    pub tokens: Vec<Token>,
+    /// `text` split into kanji runs with their readings, built from `tokens`.
+    #[serde(default)]
+    pub furigana: Vec<FuriganaSegment>,
+}
+
+impl OcrBox {
+    /// A box without text, at a scale factor of 1.
+    pub fn new(x: u32, y: u32, width: u32, height: u32) -> Self {
+        let (left, top) = (x as f64, y as f64);
+        let (right, bottom) = (left + width as f64, top + height as f64);
+        let mut ocr_box = Self {
+            text: String::new(),
+            x,
+            y,
+            width,
+            height,
+            logical: Rect::default(),
+            quad: [
+                Point::new(left, top),
+                Point::new(right, top),
+                Point::new(right, bottom),
+                Point::new(left, bottom),
+            ],
+            tokens: Vec::new(),
+            furigana: Vec::new(),
+        };
+        ocr_box.apply_scale_factor(1.0);
+        ocr_box
+    }
+
+    /// A box without text around a possibly rotated quad. Corners must not be negative.
+    pub fn from_quad(quad: [Point; 4]) -> Self {
+        let bounds = Rect::bounding(&quad);
+        let (left, top) = (bounds.x.floor(), bounds.y.floor());
+        let right = (bounds.x + bounds.width).ceil();
+        let bottom = (bounds.y + bounds.height).ceil();
+        let mut ocr_box = Self::new(
+            left as u32,
+            top as u32,
+            (right - left) as u32,
+            (bottom - top) as u32,
+        );
+        ocr_box.quad = quad;
+        ocr_box
+    }
+
+    /// Moves the box, e.g. from a cropped region back onto the whole capture.
+    pub fn translate(&mut self, x: u32, y: u32) {
+        self.x += x;
+        self.y += y;
+        for corner in &mut self.quad {
+            corner.x += x as f64;
+            corner.y += y as f64;
+        }
+    }
+
+    pub fn physical_rect(&self) -> Rect {
+        Rect {
+            x: self.x as f64,
+            y: self.y as f64,
+            width: self.width as f64,
+            height: self.height as f64,
+        }
+    }
+
+    /// Recomputes `logical` from the physical coordinates.
+    pub fn apply_scale_factor(&mut self, scale_factor: f64) {
+        self.logical = self.physical_rect().to_logical(scale_factor);
+    }
+}
+
+/// A morpheme of a recognized line. `start` and `end` are character offsets
+/// into the line's text.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Token {
+    pub surface: String,
+    /// Dictionary form, e.g. `食べる` for `食べ`.
+    pub base_form: String,
+    /// Katakana reading; unknown words have none.
+    pub reading: Option<String>,
+    /// Part of speech followed by its subcategories, e.g. `["名詞", "固有名詞", "人名"]`.
+    pub part_of_speech: Vec<String>,
+    pub conjugation_type: Option<String>,
+    pub conjugation_form: Option<String>,
+    pub start: usize,
+    pub end: usize,
+}
+
+/// A piece of text with the hiragana reading to render above it, if any.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+pub struct FuriganaSegment {
+    pub text: String,
+    pub reading: Option<String>,
+}
+
+impl FuriganaSegment {
+    pub fn plain(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            reading: None,
+        }
+    }
+}
+
+/// Lines read together, such as the columns of a speech bubble.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TextBlock {
+    /// Text of every line, in reading order.
+    pub text: String,
+    /// Whether the lines are columns read top to bottom, right to left.
+    pub vertical: bool,
+    /// Bounds of the lines in physical pixels, `logical` is the same in logical pixels.
+    pub bounds: Rect,
+    pub logical: Rect,
+    /// Indices of the lines in the result's `boxes`, in reading order.
+    pub lines: Vec<usize>,
+}
+
+/// Payload of the `run-ocr` event.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct OcrResult {
+    pub monitor_id: u32,
+    pub scale_factor: f64,
+    pub boxes: Vec<OcrBox>,
+    /// `boxes` grouped into blocks, in reading order.
+    #[serde(default)]
+    pub blocks: Vec<TextBlock>,
+}
+
+impl OcrResult {
+    pub fn new(
+        monitor_id: u32,
+        scale_factor: f64,
+        mut boxes: Vec<OcrBox>,
+        mut blocks: Vec<TextBlock>,
+    ) -> Self {
+        for ocr_box in &mut boxes {
+            ocr_box.apply_scale_factor(scale_factor);
+        }
+        for block in &mut blocks {
+            block.logical = block.bounds.to_logical(scale_factor);
+        }
+        Self {
+            monitor_id,
+            scale_factor,
+            boxes,
+            blocks,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Word {
+    pub id: i64,
+    pub word: String,
+    pub meaning: String,
+    pub screenshot: String,
+    pub translated_text: String,
+    pub source_language: String,
+    pub target_language: String,
+    pub created_at: String,
+    pub is_favorite: bool,
+    pub group_ids: Json<Vec<i64>>,
+    pub tags: Json<Vec<String>>,
+    pub pronunciation: Option<String>,
+    pub examples: Json<Vec<String>>,
+    pub notes: Option<String>,
+    /// The capture box the word was read from, if it was saved from the overlay.
+    pub source_box_id: Option<i64>,
+}
+
+/// The review schedule of a word after it was graded.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReviewState {
+    pub word_id: i64,
+    pub ease: f64,
+    pub interval_days: i64,
+    pub repetitions: i64,
+    pub due_at: String,
+}
+
+/// Totals shown on the statistics page. Dates are in the local timezone.
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UserStats {
+    pub total_words: i64,
+    pub words_this_week: i64,
+    pub words_this_month: i64,
+    pub favorite_count: i64,
+    pub group_count: i64,
+    /// Days on which at least one word was reviewed.
+    pub study_sessions: i64,
+    pub last_study_date: Option<String>,
+    #[sqlx(skip)]
+    pub daily: Vec<DailyActivity>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DailyActivity {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub words_added: i64,
+    pub reviews: i64,
+}
+
+/// Fields accepted when creating or updating a word.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NewWord {
+    pub word: String,
+    #[serde(default)]
+    pub meaning: String,
+    #[serde(default)]
+    pub screenshot: String,
+    #[serde(default)]
+    pub translated_text: String,
+    #[serde(default = "default_source_language")]
+    pub source_language: String,
+    #[serde(default = "default_target_language")]
+    pub target_language: String,
+    #[serde(default)]
+    pub is_favorite: bool,
+    #[serde(default)]
+    pub group_ids: Vec<i64>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    pub pronunciation: Option<String>,
+    #[serde(default)]
+    pub examples: Vec<String>,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub source_box_id: Option<i64>,
+}
+
+fn default_source_language() -> String {
+    "ja".to_string()
+}
+
+fn default_target_language() -> String {
+    "en".to_string()
+}
+
+/// Filters for `search_words`; every field is optional.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WordFilter {
+    /// Matched against the word, its meaning, pronunciation and notes.
+    pub query: Option<String>,
+    pub favorites_only: bool,
+    pub group_id: Option<i64>,
+    pub tag: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
-pub struct Todo {
+#[serde(rename_all = "camelCase")]
+pub struct WordGroup {
     pub id: i64,
-    pub title: String,
-    pub status: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub color: String,
     pub created_at: String,
+    pub word_count: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NewWordGroup {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub color: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub word_count: i64,
+}
+
+/// A point in either physical or logical (CSS) pixels.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct Point {
+    pub x: f64,
+    pub y: f64,
+}
+
+impl Point {
+    pub fn new(x: f64, y: f64) -> Self {
+        Self { x, y }
+    }
+
+    pub fn distance(self, other: Self) -> f64 {
+        (self.x - other.x).hypot(self.y - other.y)
+    }
+}
+
+/// A rectangle in either physical or logical (CSS) pixels.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Rect {
+    /// The smallest axis-aligned rectangle containing every point.
+    pub fn bounding(points: &[Point]) -> Self {
+        let left = points.iter().map(|p| p.x).fold(f64::MAX, f64::min);
+        let top = points.iter().map(|p| p.y).fold(f64::MAX, f64::min);
+        let right = points.iter().map(|p| p.x).fold(f64::MIN, f64::max);
+        let bottom = points.iter().map(|p| p.y).fold(f64::MIN, f64::max);
+        Self {
+            x: left,
+            y: top,
+            width: right - left,
+            height: bottom - top,
+        }
+    }
+
+    /// Physical pixels to logical pixels. A non-positive scale factor is treated as 1.
+    pub fn to_logical(self, scale_factor: f64) -> Self {
+        self.scaled(1.0 / normalized_scale_factor(scale_factor))
+    }
+
+    /// Logical pixels to physical pixels. A non-positive scale factor is treated as 1.
+    pub fn to_physical(self, scale_factor: f64) -> Self {
+        self.scaled(normalized_scale_factor(scale_factor))
+    }
+
+    /// The same rectangle with a non-negative width and height.
+    pub fn normalized(self) -> Self {
+        Self {
+            x: self.x.min(self.x + self.width),
+            y: self.y.min(self.y + self.height),
+            width: self.width.abs(),
+            height: self.height.abs(),
+        }
+    }
+
+    fn scaled(self, factor: f64) -> Self {
+        Self {
+            x: self.x * factor,
+            y: self.y * factor,
+            width: self.width * factor,
+            height: self.height * factor,
+        }
+    }
+}
+
+fn normalized_scale_factor(scale_factor: f64) -> f64 {
+    if scale_factor.is_finite() && scale_factor > 0.0 {
+        scale_factor
+    } else {
+        1.0
+    }
+}
+
+/// Ids a capture was saved under, sent to the overlay so words saved from
+/// it can point at the line they were read in.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SavedCapture {
+    pub capture_id: i64,
+    /// Id of every box, in the order of `OcrResult::boxes`.
+    pub box_ids: Vec<i64>,
+}
+
+/// A monitor that can be captured, for choosing per-monitor settings.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct MonitorInfo {
+    /// The id captures are recorded with.
+    pub id: u32,
+    /// What per-monitor settings are saved under, see `capture::monitor_key`.
+    pub key: String,
+    pub name: String,
+    pub is_primary: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum CaptureMode {
+    /// OCR the whole monitor as soon as the shortcut is pressed.
+    #[default]
+    Full,
+    /// Let the user drag a rectangle on the overlay and OCR only that region.
+    Region,
+}
+
+/// How screenshots are binarized before text detection, see `ocr::binarize`.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum Preprocessing {
+    /// Otsu for clean two-tone images, Sauvola for everything else.
+    #[default]
+    Auto,
+    /// The grayscale image as is, for images thresholding loses text in.
+    None,
+    /// One global threshold, for flat UIs and clean scans.
+    Otsu,
+    /// A local threshold, for gradients, screentones and colored panels.
+    Sauvola,
+}
+
+/// How the lines of a capture are read.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReadingOrder {
+    /// Each block is read the way most of its lines are laid out.
+    #[default]
+    Auto,
+    /// Columns top to bottom, right to left, with blocks read right to left.
+    VerticalRl,
+    /// Rows left to right, top to bottom, with blocks read left to right.
+    HorizontalTb,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Capture {
+    pub id: i64,
+    pub image_path: String,
+    pub width: i64,
+    pub height: i64,
+    pub monitor_id: i64,
+    pub scale_factor: f64,
+    pub created_at: String,
+    /// Text of every box, one line per box.
+    pub text: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, FromRow, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureBox {
+    pub id: i64,
+    pub capture_id: i64,
+    pub position: i64,
+    pub text: String,
+    pub x: i64,
+    pub y: i64,
+    pub width: i64,
+    pub height: i64,
+    /// Index of the block the line was read in, `None` for captures from before blocks.
+    pub block: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CaptureDetail {
+    #[serde(flatten)]
+    pub capture: Capture,
+    pub boxes: Vec<CaptureBox>,
+}
+
+/// A JMdict entry. Part-of-speech, field and misc values are the JMdict
+/// entity codes (`v1`, `adj-i`, `uk`, ...).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DictionaryEntry {
+    /// JMdict `ent_seq`.
+    pub id: i64,
+    pub kanji: Vec<KanjiForm>,
+    pub readings: Vec<ReadingForm>,
+    pub senses: Vec<Sense>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct KanjiForm {
+    pub text: String,
+    pub info: Vec<String>,
+    /// Priority tags such as `news1`, `ichi1` or `nf12`.
+    pub priorities: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ReadingForm {
+    pub text: String,
+    /// The reading is not a true reading of any kanji form.
+    pub no_kanji: bool,
+    /// Kanji forms this reading applies to; empty means all of them.
+    pub restrictions: Vec<String>,
+    pub info: Vec<String>,
+    pub priorities: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct Sense {
+    pub parts_of_speech: Vec<String>,
+    pub glosses: Vec<String>,
+    pub fields: Vec<String>,
+    pub misc: Vec<String>,
+    pub dialects: Vec<String>,
+    pub info: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DictionaryMatch {
+    /// The start of the looked up text that was matched, as written.
+    pub source: String,
+    /// The dictionary form `source` was deinflected to.
+    pub matched: String,
+    /// Inflections undone to get from `matched` to `source`, from the
+    /// dictionary form outwards, e.g. `["passive", "negative", "past"]`.
+    pub inflections: Vec<String>,
+    pub entry: DictionaryEntry,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, FromRow)]
+#[serde(rename_all = "camelCase")]
+pub struct DictionaryStatus {
+    pub entry_count: i64,
+    pub source: Option<String>,
+    pub imported_at: Option<String>,
+}
+
+/// A KANJIDIC2 character. Readings keep the KANJIDIC2 notation, e.g. `た.べる`
+/// with the okurigana after the dot.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct KanjiInfo {
+    pub literal: String,
+    pub on_readings: Vec<String>,
+    pub kun_readings: Vec<String>,
+    /// Readings only used in names.
+    pub nanori: Vec<String>,
+    pub meanings: Vec<String>,
+    pub stroke_count: Option<u32>,
+    /// 1-6 for the kyouiku grades, 8 for the rest of the jouyou kanji, 9-10 for jinmeiyou.
+    pub grade: Option<u32>,
+    /// Level in the pre-2010 four level JLPT.
+    pub jlpt: Option<u32>,
+    /// Rank among the 2500 most used kanji in newspapers.
+    pub frequency: Option<u32>,
+    /// Classical (Kangxi) radical number.
+    pub radical: Option<u32>,
+    /// SVG path data (109x109 viewBox) of each stroke in order, when KanjiVG was imported.
+    #[serde(default)]
+    pub strokes: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AnkiExport {
+    pub path: String,
+    pub note_count: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AnkiSendResult {
+    pub added: usize,
+    /// Already in the deck, so not sent again.
+    pub duplicates: usize,
+    pub failed: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rect(x: f64, y: f64, width: f64, height: f64) -> Rect {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    #[test]
+    fn converts_between_physical_and_logical_pixels() {
+        let physical = rect(300.0, 150.0, 600.0, 90.0);
+        assert_eq!(physical.to_logical(1.0), physical);
+        assert_eq!(physical.to_logical(1.5), rect(200.0, 100.0, 400.0, 60.0));
+        assert_eq!(physical.to_logical(2.0), rect(150.0, 75.0, 300.0, 45.0));
+
+        let logical = rect(200.0, 100.0, 400.0, 60.0);
+        assert_eq!(logical.to_physical(1.0), logical);
+        assert_eq!(logical.to_physical(1.5), rect(300.0, 150.0, 600.0, 90.0));
+        assert_eq!(logical.to_physical(2.0), rect(400.0, 200.0, 800.0, 120.0));
+    }
+
+    #[test]
+    fn round_trips_through_logical_pixels() {
+        let physical = rect(123.0, 45.0, 678.0, 90.0);
+        for scale_factor in [1.0, 1.25, 1.5, 2.0] {
+            let round_trip = physical.to_logical(scale_factor).to_physical(scale_factor);
+            assert!((round_trip.x - physical.x).abs() < 1e-9);
+            assert!((round_trip.y - physical.y).abs() < 1e-9);
+            assert!((round_trip.width - physical.width).abs() < 1e-9);
+            assert!((round_trip.height - physical.height).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn normalizes_negative_sizes() {
+        // Dragged up and to the left
+        assert_eq!(
+            rect(100.0, 80.0, -40.0, -30.0).normalized(),
+            rect(60.0, 50.0, 40.0, 30.0)
+        );
+        assert_eq!(
+            rect(100.0, 80.0, 40.0, -30.0).normalized(),
+            rect(100.0, 50.0, 40.0, 30.0)
+        );
+        assert_eq!(
+            rect(10.0, 20.0, 30.0, 40.0).normalized(),
+            rect(10.0, 20.0, 30.0, 40.0)
+        );
+    }
+
+    #[test]
+    fn treats_non_positive_scale_factors_as_one() {
+        let physical = rect(10.0, 20.0, 30.0, 40.0);
+        for scale_factor in [0.0, -2.0, f64::NAN, f64::INFINITY] {
+            assert_eq!(physical.to_logical(scale_factor), physical);
+            assert_eq!(physical.to_physical(scale_factor), physical);
+        }
+    }
+
+    #[test]
+    fn ocr_result_fills_in_logical_boxes() {
+        let result = OcrResult::new(1, 2.0, vec![OcrBox::new(100, 50, 200, 40)], Vec::new());
+        assert_eq!(result.boxes[0].logical, rect(50.0, 25.0, 100.0, 20.0));
+        // The physical box is left alone
+        assert_eq!(
+            result.boxes[0].physical_rect(),
+            rect(100.0, 50.0, 200.0, 40.0)
+        );
+    }
 }
@@ -0,0 +1,225 @@
+use crate::models::Preprocessing;
+use image::{imageops, GrayImage, Luma};
+use imageproc::contrast::{threshold, ThresholdType};
+use imageproc::filter::gaussian_blur_f32;
+use imageproc::integral_image::{integral_image, integral_squared_image, sum_image_pixels};
+use imageproc::stats::histogram;
+
+/// Images whose Otsu split explains at least this share of the variance are
+/// clean enough for one global threshold.
+const MIN_SEPARABILITY: f64 = 0.8;
+
+/// Sauvola's window is `2 * SAUVOLA_RADIUS + 1` pixels wide, about a line of
+/// text at the detector's resolution.
+const SAUVOLA_RADIUS: u32 = 15;
+const SAUVOLA_K: f64 = 0.2;
+/// Largest standard deviation of 8-bit pixels.
+const SAUVOLA_R: f64 = 128.0;
+
+/// What the histogram says about an image.
+struct HistogramStats {
+    otsu_level: u8,
+    /// Between-class variance of the Otsu split over the total variance, 1 for two flat colors.
+    separability: f64,
+}
+
+fn histogram_stats(image: &GrayImage) -> HistogramStats {
+    let counts = histogram(image).channels[0];
+    let total = counts
+        .iter()
+        .map(|&count| count as f64)
+        .sum::<f64>()
+        .max(1.0);
+    let total_sum = counts
+        .iter()
+        .enumerate()
+        .map(|(level, &count)| level as f64 * count as f64)
+        .sum::<f64>();
+    let mean = total_sum / total;
+    let variance = counts
+        .iter()
+        .enumerate()
+        .map(|(level, &count)| (level as f64 - mean).powi(2) * count as f64)
+        .sum::<f64>()
+        / total;
+
+    // Otsu: the level maximizing the variance between the two classes
+    let (mut below, mut below_sum) = (0.0, 0.0);
+    let (mut otsu_level, mut between) = (0u8, 0.0);
+    for (level, &count) in counts.iter().enumerate() {
+        below += count as f64;
+        below_sum += level as f64 * count as f64;
+        let above = total - below;
+        if below == 0.0 || above == 0.0 {
+            continue;
+        }
+        let difference = below_sum / below - (total_sum - below_sum) / above;
+        let class_variance = below * above * difference.powi(2) / (total * total);
+        if class_variance > between {
+            between = class_variance;
+            otsu_level = level as u8;
+        }
+    }
+
+    // A single flat color is as separable as it gets
+    let separability = if variance > 0.0 {
+        between / variance
+    } else {
+        1.0
+    };
+    HistogramStats {
+        otsu_level,
+        separability,
+    }
+}
+
+/// Pixels within `SAUVOLA_RADIUS` of `(x, y)`, as inclusive bounds.
+fn window(x: u32, y: u32, width: u32, height: u32) -> (u32, u32, u32, u32) {
+    (
+        x.saturating_sub(SAUVOLA_RADIUS),
+        y.saturating_sub(SAUVOLA_RADIUS),
+        (x + SAUVOLA_RADIUS).min(width - 1),
+        (y + SAUVOLA_RADIUS).min(height - 1),
+    )
+}
+
+/// Whether the text is darker than its surroundings. Text pixels stray much
+/// further from their local mean than the background does, so the sign of
+/// the summed cubed deviations tells which side they are on, gradients or not.
+fn is_dark_text(image: &GrayImage) -> bool {
+    let integral = integral_image::<_, u64>(image);
+    let (width, height) = image.dimensions();
+    let skew = image
+        .enumerate_pixels()
+        .map(|(x, y, pixel)| {
+            let (left, top, right, bottom) = window(x, y, width, height);
+            let count = ((right - left + 1) * (bottom - top + 1)) as f64;
+            let mean = sum_image_pixels(&integral, left, top, right, bottom)[0] as f64 / count;
+            (pixel[0] as f64 - mean).powi(3)
+        })
+        .sum::<f64>();
+    skew <= 0.0
+}
+
+/// Blurs and binarizes `image` into white text on a black background. The
+/// text is taken to be what stands out from its surroundings, so light text
+/// on dark UIs ends up the same way as dark text on paper.
+/// `Preprocessing::None` returns the image untouched.
+pub fn binarize(image: &GrayImage, preprocessing: Preprocessing) -> GrayImage {
+    if preprocessing == Preprocessing::None {
+        return image.clone();
+    }
+
+    // cv2.GaussianBlur(gray, (5, 5), 0), so noise does not survive as specks
+    let image = &gaussian_blur_f32(image, 1.0);
+    let stats = histogram_stats(image);
+    let preprocessing = match preprocessing {
+        Preprocessing::Auto if stats.separability >= MIN_SEPARABILITY => Preprocessing::Otsu,
+        Preprocessing::Auto => Preprocessing::Sauvola,
+        preprocessing => preprocessing,
+    };
+
+    match preprocessing {
+        Preprocessing::Otsu => {
+            let threshold_type = if is_dark_text(image) {
+                ThresholdType::BinaryInverted
+            } else {
+                ThresholdType::Binary
+            };
+            threshold(image, stats.otsu_level, threshold_type)
+        }
+        Preprocessing::Sauvola => sauvola(image, is_dark_text(image)),
+        Preprocessing::Auto | Preprocessing::None => image.clone(),
+    }
+}
+
+/// Sauvola's local threshold `mean * (1 + k * (deviation / R - 1))`, which
+/// follows gradients and screentones a global level cannot.
+fn sauvola(image: &GrayImage, dark_text: bool) -> GrayImage {
+    let mut source = image.clone();
+    if !dark_text {
+        imageops::invert(&mut source);
+    }
+    let integral = integral_image::<_, u64>(&source);
+    let squared = integral_squared_image::<_, u64>(&source);
+
+    let (width, height) = source.dimensions();
+    GrayImage::from_fn(width, height, |x, y| {
+        let (left, top, right, bottom) = window(x, y, width, height);
+        let count = ((right - left + 1) * (bottom - top + 1)) as f64;
+        let sum = sum_image_pixels(&integral, left, top, right, bottom)[0] as f64;
+        let sum_squared = sum_image_pixels(&squared, left, top, right, bottom)[0] as f64;
+        let mean = sum / count;
+        let deviation = (sum_squared / count - mean * mean).max(0.0).sqrt();
+        let level = mean * (1.0 + SAUVOLA_K * (deviation / SAUVOLA_R - 1.0));
+
+        if (source.get_pixel(x, y)[0] as f64) <= level {
+            Luma([255u8])
+        } else {
+            Luma([0u8])
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WIDTH: u32 = 200;
+    const HEIGHT: u32 = 100;
+
+    /// Vertical strokes 6 pixels wide every 16, in a band across the middle.
+    fn is_ink(x: u32, y: u32) -> bool {
+        (20..180).contains(&x) && (20..80).contains(&y) && (4..10).contains(&(x % 16))
+    }
+
+    fn page(background: impl Fn(u32) -> u8, ink: impl Fn(u32) -> u8) -> GrayImage {
+        GrayImage::from_fn(WIDTH, HEIGHT, |x, y| {
+            Luma([if is_ink(x, y) { ink(x) } else { background(x) }])
+        })
+    }
+
+    /// Stroke centers are white and the space between and around them black.
+    fn assert_white_text_on_black(binary: &GrayImage) {
+        for x in (20..180).filter(|x| x % 16 == 7) {
+            assert_eq!(binary.get_pixel(x, 50)[0], 255, "stroke at x = {}", x);
+            assert_eq!(binary.get_pixel(x + 6, 50)[0], 0, "gap at x = {}", x + 6);
+            assert_eq!(binary.get_pixel(x, 5)[0], 0, "margin at x = {}", x);
+        }
+    }
+
+    #[test]
+    fn thresholds_dark_text_on_white_globally() {
+        let image = page(|_| 255, |_| 0);
+        let binary = binarize(&image, Preprocessing::Auto);
+        assert_eq!(binary, binarize(&image, Preprocessing::Otsu));
+        assert_white_text_on_black(&binary);
+        assert_white_text_on_black(&binarize(&image, Preprocessing::Sauvola));
+    }
+
+    #[test]
+    fn inverts_light_text_on_a_dark_ui() {
+        let image = page(|_| 30, |_| 220);
+        let binary = binarize(&image, Preprocessing::Auto);
+        assert_eq!(binary, binarize(&image, Preprocessing::Otsu));
+        assert_white_text_on_black(&binary);
+        assert_white_text_on_black(&binarize(&image, Preprocessing::Sauvola));
+    }
+
+    #[test]
+    fn thresholds_text_on_a_gradient_locally() {
+        let background = |x: u32| (80 + 175 * x / (WIDTH - 1)) as u8;
+        let image = page(background, |x| background(x) - 70);
+        assert!(histogram_stats(&image).separability < MIN_SEPARABILITY);
+
+        let binary = binarize(&image, Preprocessing::Auto);
+        assert_eq!(binary, binarize(&image, Preprocessing::Sauvola));
+        assert_white_text_on_black(&binary);
+    }
+
+    #[test]
+    fn leaves_the_image_as_is_without_preprocessing() {
+        let image = page(|_| 255, |_| 0);
+        assert_eq!(binarize(&image, Preprocessing::None), image);
+    }
+}
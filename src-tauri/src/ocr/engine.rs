@@ -0,0 +1,207 @@
+use crate::{
+    japanese::{furigana, Segmenter},
+    models::{OcrBox, Preprocessing},
+    ocr::{manga_ocr, pp_ocr, rectify},
+    Result,
+};
+use image::DynamicImage;
+use ort::session::Session;
+use std::path::{Path, PathBuf};
+use tokenizers::Tokenizer;
+
+/// The debug stages reported to a [`DebugObserver`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DebugStage {
+    /// The padded detector input followed by the thresholded heatmap.
+    Detection,
+    /// The preprocessed image followed by every rectified crop sent to the recognizer.
+    Recognition,
+}
+
+/// Receives the intermediate images produced while running the pipeline.
+pub trait DebugObserver: Send {
+    fn on_debug_images(&self, stage: DebugStage, images: Vec<DynamicImage>);
+}
+
+impl<F> DebugObserver for F
+where
+    F: Fn(DebugStage, Vec<DynamicImage>) + Send,
+{
+    fn on_debug_images(&self, stage: DebugStage, images: Vec<DynamicImage>) {
+        self(stage, images)
+    }
+}
+
+/// Points `ort` at the ONNX Runtime library inside `onnx_lib_dir` and initializes it.
+pub fn init_ort_from_dir(onnx_lib_dir: &Path) -> Result<()> {
+    // --- 1. RESOLVE THE LIBRARY PATH ---
+    // Determine the filename based on the current OS
+    let lib_name = if cfg!(target_os = "windows") {
+        "onnxruntime.dll"
+    } else if cfg!(target_os = "macos") {
+        "libonnxruntime.dylib"
+    } else {
+        "libonnxruntime.so"
+    };
+
+    let lib_path = onnx_lib_dir.join(lib_name);
+
+    // Verify the file actually exists (good for debugging)
+    if !lib_path.exists() {
+        return Err(crate::Error::Other(format!(
+            "Critical Error: ONNX Library not found at path: {:?}",
+            lib_path
+        )));
+    }
+
+    // --- 2. TELL 'ORT' WHERE TO LOOK ---
+    // We set the environment variable that 'ort' checks during init
+    std::env::set_var("ORT_DYLIB_PATH", &lib_path);
+
+    // --- 3. INITIALIZE ORT ---
+    ort::init().with_name("Manga-OCR").commit()?;
+    Ok(())
+}
+
+/// Locations of the model files used by [`OcrEngine`].
+#[derive(Debug, Clone)]
+pub struct OcrModelPaths {
+    pub det_model: PathBuf,
+    pub enc_model: PathBuf,
+    pub dec_model: PathBuf,
+    pub tokenizer: PathBuf,
+    /// Compiled Lindera IPADIC directory used to split lines into words.
+    pub segmenter_dictionary: PathBuf,
+}
+
+impl OcrModelPaths {
+    /// Directory holding the ONNX Runtime library under `<resource_dir>/resources`.
+    pub fn onnx_lib_dir(resource_dir: &Path) -> PathBuf {
+        resource_dir.join("resources").join("onnx-libs")
+    }
+
+    /// Resolves the bundled models under `<resource_dir>/resources`.
+    pub fn from_resource_dir(resource_dir: &Path) -> Self {
+        let resources = resource_dir.join("resources");
+        let manga_ocr_path = resources.join("manga_ocr");
+        let pp_ocr_path = resources.join("paddle_ocr");
+
+        Self {
+            det_model: pp_ocr_path.join("ppocrv5-mobile-det.onnx"),
+            enc_model: manga_ocr_path.join("encoder_model.onnx"),
+            dec_model: manga_ocr_path.join("decoder_model.onnx"),
+            tokenizer: manga_ocr_path.join("tokenizer.json"),
+            segmenter_dictionary: resources.join("lindera").join("ipadic"),
+        }
+    }
+}
+
+/// PP-OCRv5 detection followed by Manga-OCR recognition and word
+/// segmentation, without any dependency on the Tauri runtime.
+pub struct OcrEngine {
+    det_session: Session,
+    enc_session: Session,
+    dec_session: Session,
+    tokenizer: Tokenizer,
+    segmenter: Option<Segmenter>,
+    preprocessing: Preprocessing,
+    observer: Option<Box<dyn DebugObserver>>,
+}
+
+impl OcrEngine {
+    /// Loads every session, the tokenizer and, when present, the segmentation
+    /// dictionary. `ort` must already be initialized.
+    pub fn new(paths: &OcrModelPaths) -> Result<Self> {
+        let det_session = Session::builder()?.commit_from_file(&paths.det_model)?;
+        let enc_session = Session::builder()?.commit_from_file(&paths.enc_model)?;
+        let dec_session = Session::builder()?.commit_from_file(&paths.dec_model)?;
+
+        let tokenizer = Tokenizer::from_file(&paths.tokenizer)
+            .map_err(|e| crate::Error::Tokenizer(e.to_string()))?;
+
+        // The segmentation dictionary is optional, lines are simply left unsplit without it
+        let segmenter = if paths.segmenter_dictionary.is_dir() {
+            Some(Segmenter::from_dictionary_dir(&paths.segmenter_dictionary)?)
+        } else {
+            println!(
+                "No segmentation dictionary at {:?}, skipping tokenization",
+                paths.segmenter_dictionary
+            );
+            None
+        };
+
+        Ok(Self {
+            det_session,
+            enc_session,
+            dec_session,
+            tokenizer,
+            segmenter,
+            preprocessing: Preprocessing::default(),
+            observer: None,
+        })
+    }
+
+    pub fn with_observer(mut self, observer: impl DebugObserver + 'static) -> Self {
+        self.observer = Some(Box::new(observer));
+        self
+    }
+
+    pub fn set_observer(&mut self, observer: Option<Box<dyn DebugObserver>>) {
+        self.observer = observer;
+    }
+
+    /// How images are binarized before detection, `Auto` by default.
+    pub fn set_preprocessing(&mut self, preprocessing: Preprocessing) {
+        self.preprocessing = preprocessing;
+    }
+
+    /// Detects every text line in `full_image` and recognizes its content.
+    pub fn recognize_image(&mut self, full_image: &DynamicImage) -> Result<Vec<OcrBox>> {
+        let observer = self.observer.as_deref();
+        let mut debug_images = Vec::new();
+
+        // --- 1. DETECTION (PP-OCRv5 Mobile Det) ---
+        let preprocessed_image = pp_ocr::preprocess_image(full_image, self.preprocessing);
+        if observer.is_some() {
+            debug_images.push(DynamicImage::ImageLuma8(preprocessed_image.clone()));
+        }
+
+        let detected_boxes = pp_ocr::detect(
+            &mut self.det_session,
+            &preprocessed_image,
+            full_image,
+            observer,
+        )?;
+
+        // --- 2. RECOGNITION (Manga-OCR Encoder-Decoder) ---
+        let mut final_results = Vec::new();
+
+        for mut bbox in detected_boxes {
+            // Crop the line from the original screenshot, turned upright when it is rotated
+            let cropped_image = rectify::rectify(full_image, &bbox);
+            if observer.is_some() {
+                debug_images.push(cropped_image.clone());
+            }
+
+            bbox.text = manga_ocr::recognize(
+                &mut self.enc_session,
+                &mut self.dec_session,
+                &self.tokenizer,
+                &cropped_image,
+            )?;
+
+            // --- 3. SEGMENTATION (Lindera IPADIC) and furigana ---
+            if let Some(segmenter) = &self.segmenter {
+                bbox.tokens = segmenter.tokenize(&bbox.text)?;
+                bbox.furigana = furigana::line_furigana(&bbox.text, &bbox.tokens);
+            }
+            final_results.push(bbox);
+        }
+
+        if let Some(observer) = observer {
+            observer.on_debug_images(DebugStage::Recognition, debug_images);
+        }
+
+        Ok(final_results)
+    }
+}
@@ -0,0 +1,395 @@
+use crate::models::{OcrBox, Point, ReadingOrder, Rect, TextBlock};
+use std::cmp::Ordering;
+
+/// Lines whose gap is at most this many line widths belong to the same block.
+const LINE_GAP: f64 = 1.0;
+/// Lines of the same block are at most this many times wider than each other.
+const MAX_WIDTH_RATIO: f64 = 2.0;
+/// Boxes closer than this to a square, typically a single character, fit
+/// either orientation.
+const SQUARE_RATIO: f64 = 1.5;
+
+/// How a set of rectangles is read.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flow {
+    /// Columns from right to left, each from top to bottom.
+    ColumnsRightToLeft,
+    /// Rows from top to bottom, each from left to right.
+    RowsLeftToRight,
+    /// Rows from top to bottom, each from right to left.
+    RowsRightToLeft,
+}
+
+/// Groups recognized lines into blocks such as speech bubbles, reorders
+/// `boxes` so each block's lines follow each other in `order`, and returns
+/// the blocks in the order they are read. The result only depends on the
+/// boxes' positions, not on the order detection found them in.
+pub fn group_blocks(boxes: &mut Vec<OcrBox>, order: ReadingOrder) -> Vec<TextBlock> {
+    let rects = boxes.iter().map(OcrBox::physical_rect).collect::<Vec<_>>();
+
+    let mut clusters = Clusters::new(rects.len());
+    for i in 0..rects.len() {
+        for j in i + 1..rects.len() {
+            if same_block(&rects[i], &rects[j]) {
+                clusters.join(i, j);
+            }
+        }
+    }
+
+    // Lines of each block in reading order, ignoring the single characters when voting
+    let mut blocks = clusters
+        .groups()
+        .into_iter()
+        .map(|lines| {
+            let votes = lines
+                .iter()
+                .filter(|&&i| !is_square(&rects[i]))
+                .map(|&i| if is_vertical(&rects[i]) { 1 } else { -1 })
+                .sum::<i32>();
+            let vertical = match order {
+                // Manga text is vertical unless the lines say otherwise
+                ReadingOrder::Auto => votes >= 0,
+                ReadingOrder::VerticalRl => true,
+                ReadingOrder::HorizontalTb => false,
+            };
+            let flow = if vertical {
+                Flow::ColumnsRightToLeft
+            } else {
+                Flow::RowsLeftToRight
+            };
+            let line_rects = lines.iter().map(|&i| rects[i]).collect::<Vec<_>>();
+            let order = reading_order(&line_rects, flow);
+            let lines = order.into_iter().map(|i| lines[i]).collect::<Vec<_>>();
+            (lines, vertical, bounds(&line_rects))
+        })
+        .collect::<Vec<_>>();
+
+    // Pages of vertical text, like manga, are read from the right
+    let right_to_left = match order {
+        ReadingOrder::Auto => {
+            let vertical_blocks = blocks.iter().filter(|(_, vertical, _)| *vertical).count();
+            vertical_blocks * 2 >= blocks.len()
+        }
+        ReadingOrder::VerticalRl => true,
+        ReadingOrder::HorizontalTb => false,
+    };
+    let page_flow = if right_to_left {
+        Flow::RowsRightToLeft
+    } else {
+        Flow::RowsLeftToRight
+    };
+    let block_rects = blocks.iter().map(|(_, _, rect)| *rect).collect::<Vec<_>>();
+    let block_order = reading_order(&block_rects, page_flow);
+
+    let mut taken = std::mem::take(boxes)
+        .into_iter()
+        .map(Some)
+        .collect::<Vec<_>>();
+    let mut text_blocks = Vec::with_capacity(blocks.len());
+    for index in block_order {
+        let (lines, vertical, rect) = std::mem::take(&mut blocks[index]);
+        let first = boxes.len();
+        boxes.extend(lines.iter().filter_map(|&i| taken[i].take()));
+        text_blocks.push(TextBlock {
+            text: boxes[first..]
+                .iter()
+                .map(|line| line.text.as_str())
+                .collect(),
+            vertical,
+            bounds: rect,
+            logical: rect,
+            lines: (first..boxes.len()).collect(),
+        });
+    }
+    text_blocks
+}
+
+fn is_vertical(rect: &Rect) -> bool {
+    rect.height > rect.width
+}
+
+fn is_square(rect: &Rect) -> bool {
+    let (long, short) = (rect.width.max(rect.height), rect.width.min(rect.height));
+    long < short * SQUARE_RATIO
+}
+
+/// Line width, the short side of a line box.
+fn thickness(rect: &Rect) -> f64 {
+    rect.width.min(rect.height)
+}
+
+/// Whether two lines are close enough, and alike enough, to be read together.
+fn same_block(a: &Rect, b: &Rect) -> bool {
+    let orientations_match = is_vertical(a) == is_vertical(b) || is_square(a) || is_square(b);
+    let (thin, thick) = (
+        thickness(a).min(thickness(b)),
+        thickness(a).max(thickness(b)),
+    );
+    if !orientations_match || thick > thin * MAX_WIDTH_RATIO {
+        return false;
+    }
+
+    let gap = thin * LINE_GAP;
+    a.x - gap <= b.x + b.width
+        && b.x - gap <= a.x + a.width
+        && a.y - gap <= b.y + b.height
+        && b.y - gap <= a.y + a.height
+}
+
+fn bounds(rects: &[Rect]) -> Rect {
+    let corners = rects
+        .iter()
+        .flat_map(|r| {
+            [
+                Point::new(r.x, r.y),
+                Point::new(r.x + r.width, r.y + r.height),
+            ]
+        })
+        .collect::<Vec<_>>();
+    Rect::bounding(&corners)
+}
+
+/// Indices of `rects` in the order `flow` reads them. Rectangles overlapping
+/// by more than half of the smaller one across the lane share a lane.
+fn reading_order(rects: &[Rect], flow: Flow) -> Vec<usize> {
+    // The span across the lanes and the position along them
+    let lane_span = |rect: &Rect| match flow {
+        Flow::ColumnsRightToLeft => (rect.x, rect.x + rect.width),
+        Flow::RowsLeftToRight | Flow::RowsRightToLeft => (rect.y, rect.y + rect.height),
+    };
+    let along = |rect: &Rect| match flow {
+        Flow::ColumnsRightToLeft => rect.y,
+        Flow::RowsLeftToRight => rect.x,
+        Flow::RowsRightToLeft => -(rect.x + rect.width),
+    };
+    let center = |i: usize| {
+        let (start, end) = lane_span(&rects[i]);
+        match flow {
+            Flow::ColumnsRightToLeft => -(start + end),
+            Flow::RowsLeftToRight | Flow::RowsRightToLeft => start + end,
+        }
+    };
+
+    let mut indices = (0..rects.len()).collect::<Vec<_>>();
+    indices.sort_by(|&a, &b| {
+        center(a)
+            .total_cmp(&center(b))
+            .then_with(|| by_geometry(&rects[a], &rects[b]))
+    });
+
+    let mut lanes: Vec<((f64, f64), Vec<usize>)> = Vec::new();
+    for i in indices {
+        let (start, end) = lane_span(&rects[i]);
+        let joins = lanes.last().is_some_and(|&((lane_start, lane_end), _)| {
+            let overlap = end.min(lane_end) - start.max(lane_start);
+            overlap > (end - start).min(lane_end - lane_start) / 2.0
+        });
+        match lanes.last_mut() {
+            Some((span, members)) if joins => {
+                *span = (span.0.min(start), span.1.max(end));
+                members.push(i);
+            }
+            _ => lanes.push(((start, end), vec![i])),
+        }
+    }
+
+    lanes
+        .into_iter()
+        .flat_map(|(_, mut members)| {
+            members.sort_by(|&a, &b| {
+                along(&rects[a])
+                    .total_cmp(&along(&rects[b]))
+                    .then_with(|| by_geometry(&rects[a], &rects[b]))
+            });
+            members
+        })
+        .collect()
+}
+
+/// Breaks ties between rectangles by position, then size, so the order never
+/// depends on the one they were passed in.
+fn by_geometry(a: &Rect, b: &Rect) -> Ordering {
+    a.x.total_cmp(&b.x)
+        .then(a.y.total_cmp(&b.y))
+        .then(a.width.total_cmp(&b.width))
+        .then(a.height.total_cmp(&b.height))
+}
+
+/// Union-find over line indices.
+struct Clusters {
+    parents: Vec<usize>,
+}
+
+impl Clusters {
+    fn new(len: usize) -> Self {
+        Self {
+            parents: (0..len).collect(),
+        }
+    }
+
+    fn root(&mut self, mut i: usize) -> usize {
+        while self.parents[i] != i {
+            self.parents[i] = self.parents[self.parents[i]];
+            i = self.parents[i];
+        }
+        i
+    }
+
+    fn join(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.root(a), self.root(b));
+        self.parents[a.max(b)] = a.min(b);
+    }
+
+    /// Members of every cluster, clusters ordered by their first member.
+    fn groups(mut self) -> Vec<Vec<usize>> {
+        let mut groups: Vec<Vec<usize>> = Vec::new();
+        let mut group_of_root = vec![usize::MAX; self.parents.len()];
+        for i in 0..self.parents.len() {
+            let root = self.root(i);
+            if group_of_root[root] == usize::MAX {
+                group_of_root[root] = groups.len();
+                groups.push(Vec::new());
+            }
+            groups[group_of_root[root]].push(i);
+        }
+        groups
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(text: &str, x: u32, y: u32, width: u32, height: u32) -> OcrBox {
+        let mut ocr_box = OcrBox::new(x, y, width, height);
+        ocr_box.text = text.to_string();
+        ocr_box
+    }
+
+    fn texts(boxes: &[OcrBox]) -> Vec<&str> {
+        boxes.iter().map(|line| line.text.as_str()).collect()
+    }
+
+    #[test]
+    fn merges_neighbouring_columns_read_right_to_left() {
+        let mut boxes = vec![line("左", 160, 50, 30, 200), line("右", 200, 50, 30, 200)];
+        let blocks = group_blocks(&mut boxes, ReadingOrder::Auto);
+
+        assert_eq!(blocks.len(), 1);
+        assert!(blocks[0].vertical);
+        assert_eq!(blocks[0].text, "右左");
+        assert_eq!(blocks[0].lines, [0, 1]);
+        assert_eq!(texts(&boxes), ["右", "左"]);
+        let bounds = Rect {
+            x: 160.0,
+            y: 50.0,
+            width: 70.0,
+            height: 200.0,
+        };
+        assert_eq!(blocks[0].bounds, bounds);
+    }
+
+    #[test]
+    fn keeps_separate_bubbles_apart() {
+        let mut boxes = vec![
+            line("二左", 60, 50, 30, 200),
+            line("一右", 400, 50, 30, 200),
+            line("二右", 100, 50, 30, 200),
+            line("一左", 360, 50, 30, 200),
+        ];
+        let blocks = group_blocks(&mut boxes, ReadingOrder::Auto);
+
+        let block_texts = blocks.iter().map(|b| b.text.as_str()).collect::<Vec<_>>();
+        assert_eq!(block_texts, ["一右一左", "二右二左"]);
+        assert_eq!(blocks[1].lines, [2, 3]);
+        assert_eq!(texts(&boxes), ["一右", "一左", "二右", "二左"]);
+    }
+
+    #[test]
+    fn joins_a_single_character_to_a_vertical_block() {
+        let mut boxes = vec![line("！", 200, 260, 30, 30), line("そう", 200, 50, 30, 200)];
+        let blocks = group_blocks(&mut boxes, ReadingOrder::Auto);
+
+        assert_eq!(blocks.len(), 1);
+        assert!(blocks[0].vertical);
+        assert_eq!(blocks[0].text, "そう！");
+    }
+
+    /// Every order of `items`.
+    fn permutations<T: Clone>(items: &[T]) -> Vec<Vec<T>> {
+        if items.len() <= 1 {
+            return vec![items.to_vec()];
+        }
+        (0..items.len())
+            .flat_map(|i| {
+                let mut rest = items.to_vec();
+                let first = rest.remove(i);
+                permutations(&rest).into_iter().map(move |mut tail| {
+                    tail.insert(0, first.clone());
+                    tail
+                })
+            })
+            .collect()
+    }
+
+    /// Checks that every input order of `boxes` is read as `expected`.
+    fn assert_reading_order(boxes: &[OcrBox], order: ReadingOrder, expected: &[&str]) {
+        for permutation in permutations(boxes) {
+            let mut boxes = permutation;
+            let input = texts(&boxes).join(" ");
+            group_blocks(&mut boxes, order);
+            assert_eq!(texts(&boxes), expected, "input order {}", input);
+        }
+    }
+
+    #[test]
+    fn reads_columns_right_to_left_in_any_input_order() {
+        let boxes = [
+            line("一上", 400, 50, 30, 80),
+            line("一下", 400, 140, 30, 80),
+            line("一左", 360, 50, 30, 170),
+            line("二", 100, 50, 30, 170),
+            // Shorter than the column it starts level with, so only size tells them apart
+            line("二隣", 100, 50, 30, 60),
+        ];
+        assert_reading_order(
+            &boxes,
+            ReadingOrder::VerticalRl,
+            &["一上", "一下", "一左", "二隣", "二"],
+        );
+    }
+
+    #[test]
+    fn reads_rows_top_to_bottom_in_any_input_order() {
+        let boxes = [
+            line("一行", 50, 100, 300, 30),
+            // Starts where the row does, a little lower
+            line("重", 50, 105, 100, 20),
+            line("二行", 50, 140, 300, 30),
+            line("三行", 50, 400, 300, 30),
+            line("三行後", 360, 400, 100, 30),
+        ];
+        assert_reading_order(
+            &boxes,
+            ReadingOrder::HorizontalTb,
+            &["一行", "重", "二行", "三行", "三行後"],
+        );
+    }
+
+    #[test]
+    fn reads_a_mixed_page_in_any_input_order() {
+        let boxes = [
+            line("右右", 400, 50, 30, 200),
+            line("右左", 360, 50, 30, 200),
+            line("左", 100, 50, 30, 200),
+            line("キャ", 100, 400, 100, 30),
+            line("プ", 210, 400, 100, 30),
+        ];
+        // Mostly vertical blocks, so the page is read from the right
+        assert_reading_order(
+            &boxes,
+            ReadingOrder::Auto,
+            &["右右", "右左", "左", "キャ", "プ"],
+        );
+    }
+}
@@ -1,5 +1,11 @@
+pub mod binarize;
+pub mod engine;
+pub mod layout;
 pub mod manga_ocr;
 pub mod pp_ocr;
+pub mod rectify;
+
+pub use engine::{init_ort_from_dir, DebugObserver, DebugStage, OcrEngine, OcrModelPaths};
 
 use crate::{models::OcrBox, state::AppState, Result};
 use base64::{engine::general_purpose, Engine as _};
@@ -10,89 +16,36 @@ use tauri::{AppHandle, Emitter, Manager};
 
 // In your Tauri setup / initialization
 pub fn init_ort(app_handle: &AppHandle) -> Result<()> {
-    // --- 1. RESOLVE THE LIBRARY PATH ---
     // Only do this once if possible, or check if already initialized
     let resource_path = app_handle
         .path()
         .resolve("resources/onnx-libs", BaseDirectory::Resource)
         .expect("failed to resolve resource path");
 
-    // Determine the filename based on the current OS
-    let lib_name = if cfg!(target_os = "windows") {
-        "onnxruntime.dll"
-    } else if cfg!(target_os = "macos") {
-        "libonnxruntime.dylib"
-    } else {
-        "libonnxruntime.so"
-    };
-
-    let lib_path = resource_path.join(lib_name);
-
-    // Verify the file actually exists (good for debugging)
-    if !lib_path.exists() {
-        return Err(crate::Error::Other(format!(
-            "Critical Error: ONNX Library not found at path: {:?}",
-            lib_path
-        )));
-    }
-
-    // --- 2. TELL 'ORT' WHERE TO LOOK ---
-    // We set the environment variable that 'ort' checks during init
-    let _ = std::env::set_var("ORT_DYLIB_PATH", lib_path.to_str().unwrap());
-
-    // --- 3. INITIALIZE ORT ---
-    ort::init().with_name("Manga-OCR").commit()?;
-    Ok(())
+    init_ort_from_dir(&resource_path)
 }
 
-pub async fn run_ocr(app: &AppHandle, full_image: DynamicImage) -> Result<Vec<OcrBox>> {
-    // 1. Acquire the locks
+pub async fn run_ocr(app: &AppHandle, full_image: &DynamicImage) -> Result<Vec<OcrBox>> {
     let state = app.state::<AppState>();
-    let mut det_session = state.det_session.lock().unwrap();
-    let mut enc_session = state.enc_session.lock().unwrap();
-    let mut dec_session = state.dec_session.lock().unwrap();
-    let tokenizer = state.tokenizer.lock().unwrap();
-    let mut base64_images = Vec::new();
-
-    // --- 2. DETECTION (PP-OCRv5 Mobile Det) ---
-
-    // **--- PRE-PROCESSING STEP ---**
-    let preprocessed_image = pp_ocr::preprocess_image(&full_image);
-    let base64_preprocessed_image =
-        image_buffer_to_base64(preprocessed_image.clone().into()).unwrap();
-    base64_images.push(base64_preprocessed_image);
-
-    // --- 2. DETECTION (PP-OCRv5 Mobile Det) ---
-    let detected_boxes = pp_ocr::detect(app, &mut det_session, &preprocessed_image, &full_image)?;
-
-    // --- 3. RECOGNITION (Manga-OCR Encoder-Decoder) ---
-    let mut final_results = Vec::new();
-
-    for mut bbox in detected_boxes {
-        // a. Crop the image from the original screenshot
-        let cropped_image: image::DynamicImage = full_image.clone().crop_imm(
-            bbox.x as u32,
-            bbox.y as u32,
-            bbox.width as u32,
-            bbox.height as u32,
-        );
-        let base64_cropped_image = image_buffer_to_base64(cropped_image.clone().into()).unwrap();
-        base64_images.push(base64_cropped_image);
-
-        // d. Decoder Loop (Autoregressive)
-        let recognized_text = manga_ocr::recognize(
-            &mut enc_session,
-            &mut dec_session,
-            &tokenizer,
-            &cropped_image,
-        )?;
+    let preprocessing = state.config.lock().unwrap().preprocessing;
+    let mut engine = state.engine.lock().unwrap();
+    engine.set_preprocessing(preprocessing);
+    engine.recognize_image(full_image)
+}
 
-        bbox.text = recognized_text;
-        final_results.push(bbox);
+/// Forwards the engine's debug images to the webview as base64 data URLs.
+pub fn emit_debug_images(app: AppHandle) -> impl DebugObserver {
+    move |stage: DebugStage, images: Vec<DynamicImage>| {
+        let event = match stage {
+            DebugStage::Recognition => "base64-images1",
+            DebugStage::Detection => "base64-images2",
+        };
+        let base64_images = images
+            .into_iter()
+            .filter_map(|image| image_buffer_to_base64(image).ok())
+            .collect::<Vec<_>>();
+        let _ = app.emit(event, base64_images);
     }
-
-    app.emit("base64-images1", base64_images).unwrap();
-    Ok(final_results)
 }
 
 pub fn image_buffer_to_base64(image_buffer: DynamicImage) -> Result<String> {
@@ -1,59 +1,56 @@
-use crate::{models::OcrBox, ocr::image_buffer_to_base64, Result};
+use crate::{
+    models::{OcrBox, Point, Preprocessing, Rect},
+    ocr::{
+        binarize,
+        engine::{DebugObserver, DebugStage},
+    },
+    Result,
+};
 use image::{
     imageops::{self, FilterType},
     DynamicImage, GrayImage,
 };
-use imageproc::contours::find_contours;
-use imageproc::filter;
+use imageproc::contours::{find_contours, BorderType};
+use imageproc::geometry;
 use ndarray::ArrayD;
 use ort::{session::Session, value::Value};
-use tauri::{AppHandle, Emitter};
+use std::f64::consts::FRAC_PI_4;
 
-const PADDING_MULTIPLIER: f64 = 2.2;
-const LINE_WIDTH_MULTIPLIER: u32 = 2;
 const PADDLE_OCR_MULTIPLIER: u32 = 32;
 const MAX_RESOLUTION: u32 = 1920;
 
-pub fn preprocess_image(input_image: &DynamicImage) -> GrayImage {
+/// Heatmap probability above which a pixel counts as text. Standard for PaddleOCR.
+const DET_THRESHOLD: f32 = 0.3;
+/// Mean probability inside a candidate box needed to keep it.
+const BOX_THRESHOLD: f32 = 0.6;
+/// DBNet is trained on text regions shrunk by this ratio, so boxes are grown
+/// back by `area * ratio / perimeter`.
+const UNCLIP_RATIO: f64 = 1.5;
+/// Shortest side, in heatmap pixels, of a box worth recognizing.
+const MIN_BOX_SIZE: f64 = 3.0;
+const MAX_CANDIDATES: usize = 1000;
+
+/// Downscales and grays the screenshot, then binarizes it as `preprocessing` says.
+pub fn preprocess_image(input_image: &DynamicImage, preprocessing: Preprocessing) -> GrayImage {
     // Resizing to make it run faster
     let resized_image = input_image.resize(MAX_RESOLUTION, MAX_RESOLUTION, FilterType::CatmullRom);
 
     // 1. cv2.cvtColor(image, cv2.COLOR_BGR2GRAY)
     let gray_image: GrayImage = resized_image.to_luma8();
 
-    // 2. cv2.GaussianBlur(gray, (5, 5), 0)
-    let gray_f32: image::ImageBuffer<image::Luma<f32>, Vec<f32>> =
-        image::ImageBuffer::from_fn(gray_image.width(), gray_image.height(), |x, y| {
-            let pixel = gray_image.get_pixel(x, y);
-            image::Luma([pixel[0] as f32])
-        });
-
-    let blurred_f32 = filter::gaussian_blur_f32(&gray_f32, 1.0);
-
-    // 3. cv2.threshold(blur, 153, 255, cv2.THRESH_BINARY_INV)
-    let threshold = 153.0;
-    let binary_image: image::GrayImage =
-        image::ImageBuffer::from_fn(blurred_f32.width(), blurred_f32.height(), |x, y| {
-            let pixel = blurred_f32.get_pixel(x, y);
-            if pixel[0] > threshold {
-                image::Luma([0u8]) // Black Background
-            } else {
-                image::Luma([255u8]) // White Text
-            }
-        });
-
-    binary_image
+    // 2. Global or local threshold, picked from the histogram for `Auto`
+    binarize::binarize(&gray_image, preprocessing)
 }
 
 pub fn detect(
-    app: &AppHandle,
     det_session: &mut Session,
     preprocessed_image: &GrayImage,
     full_image: &DynamicImage,
+    observer: Option<&dyn DebugObserver>,
 ) -> Result<Vec<OcrBox>> {
     let (h_full, w_full) = (full_image.height(), full_image.width());
 
-    let mut base64_images = Vec::new();
+    let mut debug_images = Vec::new();
     // The detection model requires input dimensions to be multiples of 32.
     // We pad the image to the nearest multiple of 32.
     let (h, w) = (preprocessed_image.height(), preprocessed_image.width());
@@ -69,7 +66,9 @@ pub fn detect(
     let mut padded_image = image::GrayImage::new(new_w, new_h);
     imageops::overlay(&mut padded_image, preprocessed_image, 0, 0);
 
-    base64_images.push(image_buffer_to_base64(padded_image.clone().into()).unwrap());
+    if observer.is_some() {
+        debug_images.push(DynamicImage::ImageLuma8(padded_image.clone()));
+    }
 
     // a. Convert the GrayImage (u8) into the required ONNX input tensor (f32, often normalized).
     let (h, w) = (
@@ -106,72 +105,320 @@ pub fn detect(
 
     let height = shape[2] as usize;
     let width = shape[3] as usize;
-    let threshold = 0.3; // Standard for PaddleOCR
     let mut binary_mask: image::GrayImage = image::ImageBuffer::new(width as u32, height as u32);
 
     for y in 0..height {
         for x in 0..width {
             let score = heatmap[y * width + x];
-            let val = if score > threshold { 255u8 } else { 0u8 };
+            let val = if score > DET_THRESHOLD { 255u8 } else { 0u8 };
             binary_mask.put_pixel(x as u32, y as u32, image::Luma([val]));
         }
     }
 
-    base64_images.push(image_buffer_to_base64(binary_mask.clone().into()).unwrap());
+    if observer.is_some() {
+        debug_images.push(DynamicImage::ImageLuma8(binary_mask.clone()));
+    }
 
-    // Find clusters of white pixels
-    let contours = find_contours::<i32>(&binary_mask);
+    // Find clusters of white pixels, holes inside them are not separate regions
+    let contours = find_contours::<i32>(&binary_mask)
+        .into_iter()
+        .filter(|contour| contour.border_type == BorderType::Outer)
+        .take(MAX_CANDIDATES);
 
     let mut detected_boxes = Vec::new();
 
     for contour in contours {
-        // 1. Calculate the bounding box for this contour
-        let mut min_x = i32::MAX;
-        let mut max_x = i32::MIN;
-        let mut min_y = i32::MAX;
-        let mut max_y = i32::MIN;
-
-        for pt in contour.points {
-            min_x = min_x.min(pt.x);
-            max_x = max_x.max(pt.x);
-            min_y = min_y.min(pt.y);
-            max_y = max_y.max(pt.y);
+        let quad = min_area_quad(&contour.points);
+        if short_side(&quad) < MIN_BOX_SIZE
+            || box_score(heatmap, width, height, &quad) < BOX_THRESHOLD
+        {
+            continue;
         }
 
-        min_x = ((min_x as f64) * multiplier).ceil() as i32;
-        max_x = ((max_x as f64) * multiplier).ceil() as i32;
-        min_y = ((min_y as f64) * multiplier).ceil() as i32;
-        max_y = ((max_y as f64) * multiplier).ceil() as i32;
-
-        let box_width = (max_x - min_x) as u32;
-        let box_height = (max_y - min_y) as u32;
-
-        if box_width > 5 && box_height > 5 {
-            if box_height > box_width {
-                let pixel_padding =
-                    ((box_width as f64 * PADDING_MULTIPLIER).ceil() as u32) - box_width;
-                detected_boxes.push(OcrBox {
-                    x: min_x as u32 - (pixel_padding / LINE_WIDTH_MULTIPLIER),
-                    y: min_y as u32 - pixel_padding,
-                    width: box_width + pixel_padding,
-                    height: box_height + (pixel_padding * LINE_WIDTH_MULTIPLIER),
-                    text: String::new(),
-                });
-            } else {
-                let pixel_padding =
-                    ((box_height as f64 * PADDING_MULTIPLIER).ceil() as u32) - box_height;
-                detected_boxes.push(OcrBox {
-                    x: min_x as u32 - pixel_padding,
-                    y: min_y as u32 - (pixel_padding / LINE_WIDTH_MULTIPLIER),
-                    width: box_width + (pixel_padding * LINE_WIDTH_MULTIPLIER),
-                    height: box_height + pixel_padding,
-                    text: String::new(),
-                });
-            }
+        let quad = unclip(&quad, UNCLIP_RATIO);
+        if short_side(&quad) < MIN_BOX_SIZE + 2.0 {
+            continue;
         }
+
+        // Back to the full image, clamped to its edges
+        let quad = quad.map(|corner| {
+            Point::new(
+                (corner.x * multiplier).clamp(0.0, (w_full - 1) as f64),
+                (corner.y * multiplier).clamp(0.0, (h_full - 1) as f64),
+            )
+        });
+        detected_boxes.push(OcrBox::from_quad(quad));
     }
 
-    app.emit("base64-images2", base64_images).unwrap();
+    if let Some(observer) = observer {
+        observer.on_debug_images(DebugStage::Detection, debug_images);
+    }
 
     Ok(detected_boxes)
 }
+
+/// The smallest rectangle around `points`, clockwise from the top left of
+/// the text inside. `min_area_rect` starts from its leftmost corners, which
+/// is the bottom of a column leaning left, so the top is taken to be the
+/// edge turned at most 45° from horizontal.
+pub(crate) fn min_area_quad(points: &[imageproc::point::Point<i32>]) -> [Point; 4] {
+    let mut quad =
+        geometry::min_area_rect(points).map(|corner| Point::new(corner.x as f64, corner.y as f64));
+
+    // The shoelace area is positive for clockwise corners, y pointing down
+    let area: f64 = (0..4)
+        .map(|i| {
+            let (a, b) = (quad[i], quad[(i + 1) % 4]);
+            a.x * b.y - b.x * a.y
+        })
+        .sum();
+    if area < 0.0 {
+        quad.reverse();
+    }
+
+    let top_left = (0..4)
+        .find(|&i| {
+            let (a, b) = (quad[i], quad[(i + 1) % 4]);
+            let angle = (b.y - a.y).atan2(b.x - a.x);
+            -FRAC_PI_4 < angle && angle <= FRAC_PI_4
+        })
+        .unwrap_or(0);
+    quad.rotate_left(top_left);
+    quad
+}
+
+fn short_side(quad: &[Point; 4]) -> f64 {
+    quad[0].distance(quad[1]).min(quad[1].distance(quad[2]))
+}
+
+/// Mean heatmap probability of the pixels inside the convex `quad`.
+fn box_score(heatmap: &[f32], width: usize, height: usize, quad: &[Point; 4]) -> f32 {
+    let bounds = Rect::bounding(quad);
+    let clamp = |value: f64, len: usize| (value.max(0.0) as usize).min(len - 1);
+    let (min_x, max_x) = (
+        clamp(bounds.x.floor(), width),
+        clamp((bounds.x + bounds.width).ceil(), width),
+    );
+    let (min_y, max_y) = (
+        clamp(bounds.y.floor(), height),
+        clamp((bounds.y + bounds.height).ceil(), height),
+    );
+
+    // Inside when the pixel is on the same side of every edge, whichever way they wind
+    let contains = |x: f64, y: f64| {
+        let sides = (0..4).map(|i| {
+            let (a, b) = (quad[i], quad[(i + 1) % 4]);
+            (b.x - a.x) * (y - a.y) - (b.y - a.y) * (x - a.x)
+        });
+        let (mut negative, mut positive) = (false, false);
+        for side in sides {
+            negative |= side < -1e-9;
+            positive |= side > 1e-9;
+        }
+        !(negative && positive)
+    };
+
+    let (mut sum, mut count) = (0.0, 0usize);
+    for y in min_y..=max_y {
+        for x in min_x..=max_x {
+            if contains(x as f64, y as f64) {
+                sum += heatmap[y * width + x];
+                count += 1;
+            }
+        }
+    }
+    if count == 0 {
+        0.0
+    } else {
+        sum / count as f32
+    }
+}
+
+/// Grows the rectangle `quad` outwards by `area * ratio / perimeter` on every
+/// side, undoing the shrinking DBNet's training regions went through.
+fn unclip(quad: &[Point; 4], ratio: f64) -> [Point; 4] {
+    let (width, height) = (quad[0].distance(quad[1]), quad[1].distance(quad[2]));
+    let distance = width * height * ratio / (2.0 * (width + height));
+
+    // Each corner moves away from both of its neighbours
+    std::array::from_fn(|i| {
+        let corner = quad[i];
+        let away = |neighbour: Point| {
+            let length = corner.distance(neighbour);
+            Point::new(
+                (corner.x - neighbour.x) / length * distance,
+                (corner.y - neighbour.y) / length * distance,
+            )
+        };
+        let (previous, next) = (away(quad[(i + 3) % 4]), away(quad[(i + 1) % 4]));
+        Point::new(
+            corner.x + previous.x + next.x,
+            corner.y + previous.y + next.y,
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: Point, expected: Point) {
+        assert!(
+            actual.distance(expected) < 1e-9,
+            "{:?} is not {:?}",
+            actual,
+            expected
+        );
+    }
+
+    /// The outline of a `width` by `height` rectangle turned by `degrees`
+    /// about its top left corner, which sits at (500, 500).
+    fn turned_rect(width: f64, height: f64, degrees: f64) -> Vec<imageproc::point::Point<i32>> {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let steps = (width + height) as usize * 2;
+        (0..steps)
+            .map(|step| {
+                let t = step as f64 / steps as f64 * 2.0 * (width + height);
+                let (x, y) = if t < width {
+                    (t, 0.0)
+                } else if t < width + height {
+                    (width, t - width)
+                } else if t < 2.0 * width + height {
+                    (2.0 * width + height - t, height)
+                } else {
+                    (0.0, 2.0 * (width + height) - t)
+                };
+                let (x, y) = (500.0 + x * cos - y * sin, 500.0 + x * sin + y * cos);
+                imageproc::point::Point::new(x.round() as i32, y.round() as i32)
+            })
+            .collect()
+    }
+
+    /// Width and height of `quad` as seen from its first corner.
+    fn quad_size(quad: &[Point; 4]) -> (f64, f64) {
+        (quad[0].distance(quad[1]), quad[1].distance(quad[2]))
+    }
+
+    #[test]
+    fn orders_the_corners_of_a_tilted_line_from_its_top_left() {
+        for degrees in [-30.0, -8.0, 0.0, 8.0, 30.0] {
+            let quad = min_area_quad(&turned_rect(200.0, 20.0, degrees));
+            let (width, height) = quad_size(&quad);
+            assert!(width > 190.0 && height < 30.0, "{}°: {:?}", degrees, quad);
+            assert!(
+                quad[0].distance(Point::new(500.0, 500.0)) < 3.0,
+                "{}°: {:?}",
+                degrees,
+                quad
+            );
+        }
+    }
+
+    #[test]
+    fn orders_the_corners_of_a_tilted_column_from_its_top_left() {
+        for degrees in [-15.0, -8.0, 8.0, 15.0] {
+            let quad = min_area_quad(&turned_rect(20.0, 200.0, degrees));
+            let (width, height) = quad_size(&quad);
+            assert!(width < 30.0 && height > 190.0, "{}°: {:?}", degrees, quad);
+            assert!(
+                quad[0].distance(Point::new(500.0, 500.0)) < 3.0,
+                "{}°: {:?}",
+                degrees,
+                quad
+            );
+        }
+    }
+
+    #[test]
+    fn keeps_the_top_edge_within_45_degrees_of_horizontal() {
+        // A line turned by 60° reads best as a column turned by -30°
+        let quad = min_area_quad(&turned_rect(200.0, 20.0, 60.0));
+        let (width, height) = quad_size(&quad);
+        assert!(width < 30.0 && height > 190.0, "{:?}", quad);
+    }
+
+    #[test]
+    fn unclips_every_side_by_the_same_distance() {
+        let quad = [
+            Point::new(0.0, 0.0),
+            Point::new(10.0, 0.0),
+            Point::new(10.0, 4.0),
+            Point::new(0.0, 4.0),
+        ];
+        // 40 * 2 / 28
+        let d = 20.0 / 7.0;
+        let unclipped = unclip(&quad, 2.0);
+        assert_near(unclipped[0], Point::new(-d, -d));
+        assert_near(unclipped[1], Point::new(10.0 + d, -d));
+        assert_near(unclipped[2], Point::new(10.0 + d, 4.0 + d));
+        assert_near(unclipped[3], Point::new(-d, 4.0 + d));
+    }
+
+    #[test]
+    fn unclips_a_turned_rectangle_along_its_own_sides() {
+        let (sin, cos) = 30f64.to_radians().sin_cos();
+        let turn = |x: f64, y: f64| Point::new(x * cos - y * sin, x * sin + y * cos);
+        let quad = [
+            turn(0.0, 0.0),
+            turn(10.0, 0.0),
+            turn(10.0, 4.0),
+            turn(0.0, 4.0),
+        ];
+
+        let d = 20.0 / 7.0;
+        let unclipped = unclip(&quad, 2.0);
+        assert_near(unclipped[0], turn(-d, -d));
+        assert_near(unclipped[2], turn(10.0 + d, 4.0 + d));
+        let (width, height) = quad_size(&unclipped);
+        assert!((width - (10.0 + 2.0 * d)).abs() < 1e-9);
+        assert!((height - (4.0 + 2.0 * d)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn scores_the_mean_probability_inside_the_box() {
+        // The left half of a 10x10 heatmap is text
+        let (width, height) = (10, 10);
+        let heatmap: Vec<f32> = (0..width * height)
+            .map(|i| if i % width < 5 { 1.0 } else { 0.0 })
+            .collect();
+        let quad = |left: f64, right: f64| {
+            [
+                Point::new(left, 0.0),
+                Point::new(right, 0.0),
+                Point::new(right, 9.0),
+                Point::new(left, 9.0),
+            ]
+        };
+
+        assert_eq!(box_score(&heatmap, width, height, &quad(0.0, 4.0)), 1.0);
+        assert_eq!(box_score(&heatmap, width, height, &quad(5.0, 9.0)), 0.0);
+        assert_eq!(box_score(&heatmap, width, height, &quad(0.0, 9.0)), 0.5);
+    }
+
+    #[test]
+    fn scores_only_the_pixels_inside_a_turned_box() {
+        // A diamond of text, which its axis-aligned bounds would dilute
+        let (width, height) = (11, 11);
+        let heatmap: Vec<f32> = (0..width * height)
+            .map(|i| {
+                let (x, y) = ((i % width) as i32, (i / width) as i32);
+                if (x - 5).abs() + (y - 5).abs() <= 5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let diamond = [
+            Point::new(5.0, 0.0),
+            Point::new(10.0, 5.0),
+            Point::new(5.0, 10.0),
+            Point::new(0.0, 5.0),
+        ];
+        let mut counterclockwise = diamond;
+        counterclockwise.reverse();
+
+        assert_eq!(box_score(&heatmap, width, height, &diamond), 1.0);
+        assert_eq!(box_score(&heatmap, width, height, &counterclockwise), 1.0);
+    }
+}
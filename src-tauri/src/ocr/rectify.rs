@@ -0,0 +1,206 @@
+use crate::models::{OcrBox, Point};
+use image::{DynamicImage, Rgba};
+use imageproc::geometric_transformations::{warp_into, Interpolation, Projection};
+
+/// Fills the parts of a warped crop that fall outside the captured image.
+const BACKGROUND: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/// The text inside `ocr_box`, warped so its quad becomes an upright
+/// rectangle. Vertical lines stay vertical, Manga-OCR reads them as they are.
+pub fn rectify(full_image: &DynamicImage, ocr_box: &OcrBox) -> DynamicImage {
+    // Only the box's bounds are warped, so large captures are not copied once per line
+    let bounds = full_image.crop_imm(ocr_box.x, ocr_box.y, ocr_box.width, ocr_box.height);
+    let quad = ocr_box
+        .quad
+        .map(|corner| Point::new(corner.x - ocr_box.x as f64, corner.y - ocr_box.y as f64));
+    if is_upright(&quad) {
+        return bounds;
+    }
+
+    let [top_left, top_right, bottom_right, bottom_left] = quad;
+    let width = top_left
+        .distance(top_right)
+        .max(bottom_left.distance(bottom_right))
+        .round()
+        .max(1.0);
+    let height = top_left
+        .distance(bottom_left)
+        .max(top_right.distance(bottom_right))
+        .round()
+        .max(1.0);
+
+    let from = quad.map(|corner| (corner.x as f32, corner.y as f32));
+    let to = [
+        (0.0, 0.0),
+        (width as f32, 0.0),
+        (width as f32, height as f32),
+        (0.0, height as f32),
+    ];
+    // Degenerate quads have no projection, the plain crop is the best there is
+    let Some(projection) = Projection::from_control_points(from, to) else {
+        return bounds;
+    };
+
+    let mut upright = image::RgbaImage::new(width as u32, height as u32);
+    warp_into(
+        &bounds.to_rgba8(),
+        &projection,
+        Interpolation::Bilinear,
+        BACKGROUND,
+        &mut upright,
+    );
+    DynamicImage::ImageRgba8(upright)
+}
+
+/// Whether the quad is already an axis-aligned rectangle.
+fn is_upright(quad: &[Point; 4]) -> bool {
+    const TOLERANCE: f64 = 0.5;
+    let [top_left, top_right, bottom_right, bottom_left] = quad;
+    (top_left.y - top_right.y).abs() < TOLERANCE
+        && (bottom_left.y - bottom_right.y).abs() < TOLERANCE
+        && (top_left.x - bottom_left.x).abs() < TOLERANCE
+        && (top_right.x - bottom_right.x).abs() < TOLERANCE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ocr::pp_ocr;
+    use image::{GrayImage, Luma, RgbaImage};
+    use imageproc::contours::find_contours;
+
+    const LINE_WIDTH: f64 = 120.0;
+    const LINE_HEIGHT: f64 = 40.0;
+
+    /// A full height bar and a bar over the top half only, so a crop that is
+    /// mirrored or upside down does not pass for upright.
+    fn is_ink(x: f64, y: f64) -> bool {
+        (10.0..30.0).contains(&x) || ((60.0..80.0).contains(&x) && y < LINE_HEIGHT / 2.0)
+    }
+
+    /// Where a point of the line lands once the line is turned by `degrees`
+    /// about the center of the page.
+    fn on_page(point: Point, degrees: f64, center: Point) -> Point {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        let (x, y) = (point.x - LINE_WIDTH / 2.0, point.y - LINE_HEIGHT / 2.0);
+        Point::new(center.x + x * cos - y * sin, center.y + x * sin + y * cos)
+    }
+
+    /// A white page with the bar pattern turned by `degrees`, and its quad.
+    fn rotated_line(degrees: f64) -> (DynamicImage, OcrBox) {
+        let center = Point::new(150.0, 100.0);
+        let page = RgbaImage::from_fn(300, 200, |x, y| {
+            // Back from the page into the line, the inverse rotation
+            let (sin, cos) = (-degrees).to_radians().sin_cos();
+            let (dx, dy) = (x as f64 + 0.5 - center.x, y as f64 + 0.5 - center.y);
+            let line_x = dx * cos - dy * sin + LINE_WIDTH / 2.0;
+            let line_y = dx * sin + dy * cos + LINE_HEIGHT / 2.0;
+            let inside =
+                (0.0..LINE_WIDTH).contains(&line_x) && (0.0..LINE_HEIGHT).contains(&line_y);
+            if inside && is_ink(line_x, line_y) {
+                Rgba([0, 0, 0, 255])
+            } else {
+                BACKGROUND
+            }
+        });
+        let quad = [
+            Point::new(0.0, 0.0),
+            Point::new(LINE_WIDTH, 0.0),
+            Point::new(LINE_WIDTH, LINE_HEIGHT),
+            Point::new(0.0, LINE_HEIGHT),
+        ]
+        .map(|corner| on_page(corner, degrees, center));
+        (DynamicImage::ImageRgba8(page), OcrBox::from_quad(quad))
+    }
+
+    fn assert_upright_pattern(crop: &GrayImage) {
+        let (width, height) = crop.dimensions();
+        assert!(
+            width.abs_diff(LINE_WIDTH as u32) <= 1 && height.abs_diff(LINE_HEIGHT as u32) <= 1,
+            "crop is {}x{}",
+            width,
+            height
+        );
+        // Sampled away from the edges of the bars, which interpolation blurs
+        for (x, y) in [(20, 10), (20, 30), (70, 10), (40, 20), (70, 30), (100, 20)] {
+            let Luma([value]) = *crop.get_pixel(x, y);
+            let expected = is_ink(x as f64, y as f64);
+            assert!(
+                if expected { value < 64 } else { value > 192 },
+                "pixel ({}, {}) is {}",
+                x,
+                y,
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn turns_a_clockwise_line_upright() {
+        let (page, ocr_box) = rotated_line(15.0);
+        assert_upright_pattern(&rectify(&page, &ocr_box).to_luma8());
+    }
+
+    #[test]
+    fn turns_a_counterclockwise_line_upright() {
+        let (page, ocr_box) = rotated_line(-30.0);
+        assert_upright_pattern(&rectify(&page, &ocr_box).to_luma8());
+    }
+
+    /// A page with a 20x200 column turned by `degrees`, inked over its top
+    /// half, and its box as detection finds it.
+    fn rotated_column(degrees: f64) -> (DynamicImage, OcrBox) {
+        let (sin, cos) = (-degrees).to_radians().sin_cos();
+        // Where a page pixel lands in the column, if it is inside
+        let in_column = |x: u32, y: u32| {
+            let (dx, dy) = (x as f64 + 0.5 - 150.0, y as f64 + 0.5 - 150.0);
+            let (column_x, column_y) = (dx * cos - dy * sin + 10.0, dx * sin + dy * cos + 100.0);
+            ((0.0..20.0).contains(&column_x) && (0.0..200.0).contains(&column_y))
+                .then_some(column_y)
+        };
+        let page = RgbaImage::from_fn(300, 300, |x, y| match in_column(x, y) {
+            Some(column_y) if column_y < 100.0 => Rgba([0, 0, 0, 255]),
+            _ => BACKGROUND,
+        });
+        let region = GrayImage::from_fn(300, 300, |x, y| {
+            Luma([if in_column(x, y).is_some() { 255 } else { 0 }])
+        });
+        let contour = &find_contours::<i32>(&region)[0];
+        let ocr_box = OcrBox::from_quad(pp_ocr::min_area_quad(&contour.points));
+        (DynamicImage::ImageRgba8(page), ocr_box)
+    }
+
+    #[test]
+    fn keeps_a_tilted_column_upright() {
+        for degrees in [-15.0, -8.0, 8.0, 15.0] {
+            let (page, ocr_box) = rotated_column(degrees);
+            let crop = rectify(&page, &ocr_box).to_luma8();
+            let (width, height) = crop.dimensions();
+            assert!(
+                width <= 24 && (198..=204).contains(&height),
+                "{}°: crop is {}x{}",
+                degrees,
+                width,
+                height
+            );
+            let Luma([top]) = *crop.get_pixel(width / 2, 50);
+            let Luma([bottom]) = *crop.get_pixel(width / 2, 150);
+            assert!(
+                top < 64 && bottom > 192,
+                "{}°: column is upside down",
+                degrees
+            );
+        }
+    }
+
+    #[test]
+    fn crops_an_axis_aligned_line_as_is() {
+        let (page, ocr_box) = rotated_line(0.0);
+        assert_eq!((ocr_box.width, ocr_box.height), (120, 40));
+
+        let crop = rectify(&page, &ocr_box);
+        let plain = page.crop_imm(ocr_box.x, ocr_box.y, ocr_box.width, ocr_box.height);
+        assert_eq!(crop.to_rgba8(), plain.to_rgba8());
+        assert_upright_pattern(&crop.to_luma8());
+    }
+}
@@ -0,0 +1,95 @@
+//! Global shortcuts taken from the config. They are registered again every
+//! time the config changes, so rebinding needs no restart.
+
+use crate::capture::take_screenshot;
+use crate::config::AppConfig;
+use crate::models::CaptureMode;
+use crate::state::AppState;
+use crate::{Error, Result};
+use std::sync::atomic::Ordering;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutEvent, ShortcutState};
+
+/// `Ctrl+Shift+S`, `Alt+F1`, ... Modifiers and keys are case insensitive.
+pub fn parse(text: &str) -> Result<Shortcut> {
+    text.trim()
+        .parse::<Shortcut>()
+        .map_err(|err| Error::InvalidShortcut {
+            shortcut: text.to_string(),
+            reason: err.to_string(),
+        })
+}
+
+/// Every shortcut of `config` with the capture mode it starts.
+pub fn bindings(config: &AppConfig) -> Result<Vec<(Shortcut, CaptureMode)>> {
+    let mut bindings = vec![(parse(&config.capture_shortcut)?, config.capture_mode)];
+    let region_shortcut = config
+        .region_capture_shortcut
+        .as_deref()
+        .filter(|text| !text.trim().is_empty());
+    if let Some(text) = region_shortcut {
+        let shortcut = parse(text)?;
+        if bindings.iter().any(|(bound, _)| *bound == shortcut) {
+            return Err(Error::Config(format!(
+                "{} is used for more than one shortcut",
+                text
+            )));
+        }
+        bindings.push((shortcut, CaptureMode::Region));
+    }
+    Ok(bindings)
+}
+
+/// Replaces the registered shortcuts with the ones of `config`. Whether
+/// that worked is kept in `AppState::shortcuts_registered`.
+pub fn register(app: &AppHandle, config: &AppConfig) -> Result<()> {
+    let registered = &app.state::<AppState>().shortcuts_registered;
+    registered.store(false, Ordering::SeqCst);
+
+    let global_shortcut = app.global_shortcut();
+    global_shortcut.unregister_all()?;
+    for (shortcut, _) in bindings(config)? {
+        // Most often the combination is already taken by another application
+        global_shortcut
+            .register(shortcut)
+            .map_err(|err| Error::ShortcutConflict {
+                shortcut: shortcut.to_string(),
+                reason: err.to_string(),
+            })?;
+    }
+    registered.store(true, Ordering::SeqCst);
+    Ok(())
+}
+
+/// Handler of the global shortcut plugin.
+pub fn handle(app: &AppHandle, shortcut: &Shortcut, event: ShortcutEvent) {
+    if event.state != ShortcutState::Pressed {
+        return;
+    }
+
+    let state = app.state::<AppState>();
+    let mode = bindings(&state.config.lock().unwrap())
+        .ok()
+        .and_then(|bindings| {
+            bindings
+                .into_iter()
+                .find(|(bound, _)| bound == shortcut)
+                .map(|(_, mode)| mode)
+        });
+    let Some(mode) = mode else {
+        return;
+    };
+
+    let processing = state.is_processing.clone();
+    if processing.swap(true, Ordering::SeqCst) {
+        println!("OCR process is already running, cancelling new request");
+        return; // Already running, ignore this press
+    }
+
+    let app = app.clone();
+    tauri::async_runtime::spawn(async move {
+        take_screenshot(&app, mode).await;
+        // Reset flag when done
+        processing.store(false, Ordering::SeqCst);
+    });
+}
@@ -0,0 +1,116 @@
+//! SM-2 scheduling, as described in the SuperMemo 2 paper. Grades go from 0
+//! (complete blackout) to 5 (perfect recall), anything below 3 is a lapse.
+
+pub const MAX_GRADE: u8 = 5;
+pub const PASSING_GRADE: u8 = 3;
+pub const INITIAL_EASE: f64 = 2.5;
+pub const MIN_EASE: f64 = 1.3;
+
+/// Where a word stands in its review cycle.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Schedule {
+    pub ease: f64,
+    /// Days until the next review.
+    pub interval_days: i64,
+    /// Successful reviews in a row.
+    pub repetitions: i64,
+}
+
+impl Default for Schedule {
+    /// A word that was never reviewed.
+    fn default() -> Self {
+        Self {
+            ease: INITIAL_EASE,
+            interval_days: 0,
+            repetitions: 0,
+        }
+    }
+}
+
+impl Schedule {
+    /// The schedule after answering with `grade`, which must be at most `MAX_GRADE`.
+    pub fn review(self, grade: u8) -> Self {
+        if grade < PASSING_GRADE {
+            // A lapse starts the word over without changing its ease
+            return Self {
+                interval_days: 1,
+                repetitions: 0,
+                ..self
+            };
+        }
+
+        let interval_days = match self.repetitions {
+            0 => 1,
+            1 => 6,
+            _ => (self.interval_days as f64 * self.ease).round() as i64,
+        };
+        let miss = f64::from(MAX_GRADE - grade);
+        let ease = (self.ease + 0.1 - miss * (0.08 + miss * 0.02)).max(MIN_EASE);
+
+        Self {
+            ease,
+            interval_days,
+            repetitions: self.repetitions + 1,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_schedule(schedule: Schedule, ease: f64, interval_days: i64, repetitions: i64) {
+        assert!(
+            (schedule.ease - ease).abs() < 1e-9,
+            "ease {} != {}",
+            schedule.ease,
+            ease
+        );
+        assert_eq!(schedule.interval_days, interval_days);
+        assert_eq!(schedule.repetitions, repetitions);
+    }
+
+    #[test]
+    fn perfect_answers_stretch_the_interval() {
+        let first = Schedule::default().review(5);
+        assert_schedule(first, 2.6, 1, 1);
+        let second = first.review(5);
+        assert_schedule(second, 2.7, 6, 2);
+        let third = second.review(5);
+        // 6 days times the ease before the review
+        assert_schedule(third, 2.8, 16, 3);
+    }
+
+    #[test]
+    fn lapses_start_over_with_the_same_ease() {
+        let learned = Schedule::default().review(5).review(5).review(5);
+        let lapsed = learned.review(2);
+        assert_schedule(lapsed, 2.8, 1, 0);
+        assert_schedule(lapsed.review(0), 2.8, 1, 0);
+
+        let relearned = lapsed.review(4);
+        assert_schedule(relearned, 2.8, 1, 1);
+        assert_schedule(relearned.review(4), 2.8, 6, 2);
+    }
+
+    #[test]
+    fn ease_never_drops_below_the_floor() {
+        let mut schedule = Schedule::default();
+        let eases = (0..10)
+            .map(|_| {
+                schedule = schedule.review(PASSING_GRADE);
+                schedule.ease
+            })
+            .collect::<Vec<_>>();
+        let expected = [2.36, 2.22, 2.08, 1.94, 1.8, 1.66, 1.52, 1.38, 1.3, 1.3];
+        for (ease, expected) in eases.iter().zip(expected) {
+            assert!(
+                (ease - expected).abs() < 1e-9,
+                "ease {} != {}",
+                ease,
+                expected
+            );
+        }
+        assert_eq!(schedule.ease, MIN_EASE);
+    }
+}
@@ -1,13 +1,15 @@
-use ort::session::Session;
+use crate::capture::PendingCapture;
+use crate::config::AppConfig;
+use crate::ocr::OcrEngine;
 use sqlx::{Pool, Sqlite};
 use std::sync::{atomic::AtomicBool, Arc, Mutex};
-use tokenizers::Tokenizer;
 
 pub struct AppState {
     pub db: Pool<Sqlite>,
     pub is_processing: Arc<AtomicBool>,
-    pub det_session: Mutex<Session>,
-    pub enc_session: Mutex<Session>,
-    pub dec_session: Mutex<Session>,
-    pub tokenizer: Mutex<Tokenizer>,
+    pub shortcuts_registered: AtomicBool,
+    pub engine: Mutex<OcrEngine>,
+    pub config: Mutex<AppConfig>,
+    pub pending_capture: Mutex<Option<PendingCapture>>,
+    pub dictionary_import: tokio::sync::Mutex<()>,
 }
@@ -1,45 +1,51 @@
-import { useQuery, useQueryClient } from "@tanstack/vue-query";
-import { readDir, BaseDirectory } from "@tauri-apps/plugin-fs";
-import { convertFileSrc } from "@tauri-apps/api/core";
+import { useMutation, useQuery, useQueryClient } from "@tanstack/vue-query";
+import { convertFileSrc, invoke } from "@tauri-apps/api/core";
 
-export interface Screenshot {
-  name: string;
-  path: string;
+export interface Capture {
+  id: number;
+  imagePath: string;
+  width: number;
+  height: number;
+  monitorId: number;
+  scaleFactor: number;
+  createdAt: string;
+  text: string;
+}
+
+export interface Screenshot extends Capture {
+  // WebView-safe URL of the saved image
   url: string;
 }
 
-// Inside your function:
 export function useScreenshots() {
   const queryClient = useQueryClient();
 
   const query = useQuery({
     queryKey: ["screenshots"],
     queryFn: async (): Promise<Screenshot[]> => {
-      // 1. Read files from the user's Picture directory
-      // Ensure your Rust code saves here!
-      const entries = await readDir("", { baseDir: BaseDirectory.Picture });
-
-      // 2. Filter and Map
-      const images = entries
-        .filter(
-          (entry) => entry.isFile && /\.(png|jpg|jpeg)$/i.test(entry.name),
-        )
-        .map((entry) => ({
-          name: entry.name,
-          path: entry.name, // In a real app, you might want the full path
-          // 3. Convert to a WebView-safe URL
-          url: convertFileSrc(entry.name),
-        }));
-
-      // 4. Sort (Optional: assumes timestamp naming like screenshot_2024...)
-      return images.sort((a, b) => b.name.localeCompare(a.name));
+      // Captures are saved by the Rust side under AppData/captures, newest first
+      const captures = await invoke<Capture[]>("list_captures");
+      return captures.map((capture) => ({
+        ...capture,
+        url: convertFileSrc(capture.imagePath),
+      }));
     },
     // Refetch when window creates focus (optional but nice for desktop apps)
     refetchOnWindowFocus: true,
   });
 
+  const deleteScreenshot = useMutation({
+    mutationFn: async (id: number) => {
+      await invoke("delete_capture", { id });
+    },
+    onSuccess: () => {
+      queryClient.invalidateQueries({ queryKey: ["screenshots"] });
+    },
+  });
+
   return {
     ...query,
+    deleteScreenshot,
     refresh: () => queryClient.invalidateQueries({ queryKey: ["screenshots"] }),
   };
 }
@@ -13,6 +13,7 @@ export interface Word {
   pronunciation?: string;
   examples?: string[];
   notes?: string;
+  sourceBoxId?: number;
 }
 
 export interface WordGroup {
@@ -24,20 +25,49 @@ export interface WordGroup {
   wordCount: number;
 }
 
+export interface Rect {
+  x: number;
+  y: number;
+  width: number;
+  height: number;
+}
+
+export interface MonitorInfo {
+  id: number;
+  // Stays the same across restarts, unlike `id`
+  key: string;
+  name: string;
+  isPrimary: boolean;
+}
+
+// How captured lines are sorted, `auto` follows each block's own layout
+export type ReadingOrder = "auto" | "vertical-rl" | "horizontal-tb";
+
 export interface AppConfig {
   dictionaryProvider: "free-dictionary" | "google-translate" | "custom";
   customDictionaryUrl?: string;
   sourceLanguage: string;
   targetLanguage: string;
-  ocrBounds: {
-    x: number;
-    y: number;
-    width: number;
-    height: number;
-  };
+  // Keyed by `MonitorInfo.key`, monitors without bounds are read whole
+  ocrBounds: Record<string, Rect>;
   captureShortcut: string;
+  captureMode: "full" | "region";
+  regionCaptureShortcut?: string;
+  readingOrder: ReadingOrder;
+  preprocessing: "auto" | "none" | "otsu" | "sauvola";
   autoSave: boolean;
   theme: "light" | "dark" | "system";
+  anki: AnkiConfig;
+}
+
+// Note fields are always Word, Reading, Meaning, Sentence and Screenshot
+export interface AnkiConfig {
+  deckName: string;
+  noteType: string;
+  frontTemplate: string;
+  backTemplate: string;
+  css: string;
+  connectUrl: string;
 }
 
 export interface UserStats {
@@ -48,4 +78,12 @@ export interface UserStats {
   groupCount: number;
   studySessions: number;
   lastStudyDate?: string;
+  // Last seven days, oldest first
+  daily: DailyActivity[];
+}
+
+export interface DailyActivity {
+  date: string; // YYYY-MM-DD, local time
+  wordsAdded: number;
+  reviews: number;
 }
@@ -2,7 +2,6 @@ import { createWebHistory, createRouter } from "vue-router";
 import ScreenshotPage from "./views/ScreenshotPage.vue";
 import SettingPage from "./views/SettingPage.vue";
 import NotFound from "./views/NotFound.vue";
-import TodoPage from "./views/TodoPage.vue";
 import OverlayPage from "./views/OverlayPage.vue";
 import AppLayout from "./components/AppLayout.vue";
 import DashboardPage from "./views/DashboardPage.vue";